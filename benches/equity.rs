@@ -1,5 +1,5 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion, SamplingMode};
-use poker::{prelude::*, equity::{EquityParams, equity_monte_carlo, equity_enumerate}};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, SamplingMode, Throughput};
+use poker::{prelude::*, equity::{EquityParams, equity_monte_carlo, equity_enumerate, equity_stratified}};
 use std::path::Path;
 
 // TODO: Maybe change to randomised ranges
@@ -57,6 +57,8 @@ fn benchmark_monte_carlo(c: &mut Criterion) {
                     board,
                     lookup: &lookup_table,
                     reporter: None,
+                    seed: None,
+                    cancel: None,
                 }
             },
             |params| black_box(equity_monte_carlo(params, Some(50)).unwrap())
@@ -73,6 +75,8 @@ fn benchmark_monte_carlo(c: &mut Criterion) {
                     board,
                     lookup: &lookup_table,
                     reporter: None,
+                    seed: None,
+                    cancel: None,
                 }
             },
             |params| black_box(equity_monte_carlo(params, Some(50)).unwrap())
@@ -89,6 +93,8 @@ fn benchmark_monte_carlo(c: &mut Criterion) {
                     board,
                     lookup: &lookup_table,
                     reporter: None,
+                    seed: None,
+                    cancel: None,
                 }
             },
             |params| black_box(equity_monte_carlo(params, Some(50)).unwrap())
@@ -105,6 +111,8 @@ fn benchmark_monte_carlo(c: &mut Criterion) {
                     board,
                     lookup: &lookup_table,
                     reporter: None,
+                    seed: None,
+                    cancel: None,
                 }
             },
             |params| black_box(equity_monte_carlo(params, Some(50)).unwrap())
@@ -140,6 +148,8 @@ fn benchmark_enumerate(c: &mut Criterion) {
                     board,
                     lookup: &lookup_table,
                     reporter: None,
+                    seed: None,
+                    cancel: None,
                 }
             },
             |params| black_box(equity_enumerate(params).unwrap())
@@ -156,6 +166,8 @@ fn benchmark_enumerate(c: &mut Criterion) {
                     board,
                     lookup: &lookup_table,
                     reporter: None,
+                    seed: None,
+                    cancel: None,
                 }
             },
             |params| black_box(equity_enumerate(params).unwrap())
@@ -165,9 +177,146 @@ fn benchmark_enumerate(c: &mut Criterion) {
     group.finish();
 }
 
+// Standard deviation of the first player's equity over repeated runs with different seeds.
+fn equity_std_dev<F>(runs: u64, mut run: F) -> f64 
+where
+    F: FnMut(u64) -> f64,
+{
+    let equities = (0..runs).map(&mut run).collect::<Vec<f64>>();
+    let mean = equities.iter().sum::<f64>() / runs as f64;
+    let variance = equities.iter().map(|e| (e - mean).powi(2)).sum::<f64>() / (runs - 1) as f64;
+    variance.sqrt()
+}
+
+fn benchmark_stratified(c: &mut Criterion) {
+
+    let lookup_path = std::env::var("LOOKUP_PATH").unwrap_or("data/lookup_table.bin".to_string());
+    let lookup_table = match load_lookup_table(Path::new(&lookup_path)) {
+        Ok(table) => table,
+        Err(e) => {
+            eprintln!("Failed to load lookup table: {}", e);
+            return;
+        }
+    };
+
+    const ITERATIONS: u64 = 20_000;
+    let spots = [
+        ("preflop_2p", fixed_ranges(2), Board::default()),
+        ("flop_2p", fixed_ranges(2), Board::from_str("Qh 4h 8c").unwrap()),
+        ("turn_3p", fixed_ranges(3), Board::from_str("Ts 7h 2c 4d").unwrap()),
+    ];
+
+    let params = |ranges: &Vec<Range>, board: &Board, seed| EquityParams {
+        ranges: ranges.clone(),
+        board: board.clone(),
+        lookup: &lookup_table,
+        reporter: None,
+        seed: Some(seed),
+        cancel: None,
+    };
+
+    // Variance reduction at a fixed number of samples, compare against the time per run below.
+    for (name, ranges, board) in spots.iter() {
+        let mc = equity_std_dev(100, |seed| equity_monte_carlo(params(ranges, board, seed), Some(ITERATIONS)).unwrap().equities()[0]);
+        let stratified = equity_std_dev(100, |seed| equity_stratified(params(ranges, board, seed), Some(ITERATIONS)).unwrap().equities()[0]);
+        println!(
+            "{}: equity std dev over {} samples, monte carlo {:.4}%, stratified {:.4}%, variance ratio {:.2}",
+            name, ITERATIONS, mc, stratified, (mc / stratified).powi(2),
+        );
+    }
+
+    let mut group = c.benchmark_group("stratified");
+    group.sample_size(30);
+    group.sampling_mode(SamplingMode::Flat);
+
+    for (name, ranges, board) in spots.iter() {
+        group.bench_function(format!("monte_carlo_{}", name), |b| {
+            b.iter(|| black_box(equity_monte_carlo(params(ranges, board, 0), Some(ITERATIONS)).unwrap()))
+        });
+        group.bench_function(format!("stratified_{}", name), |b| {
+            b.iter(|| black_box(equity_stratified(params(ranges, board, 0), Some(ITERATIONS)).unwrap()))
+        });
+    }
+
+    group.finish();
+}
+
+fn benchmark_runout_sampling(c: &mut Criterion) {
+
+    // Board and hole cards already in use.
+    let dead = Card::vec_from_str("Qh 4h 8c As Ad Ks Kd").unwrap().iter().fold(0, |mask, card| mask | card.mask());
+
+    let mut group = c.benchmark_group("runout_sampling");
+    group.throughput(Throughput::Elements(1));
+
+    // Previous approach, a fresh shuffled deck per iteration.
+    group.bench_function("clone_shuffle", |b| {
+        let deck = Deck::new();
+        b.iter(|| {
+            let mut local_deck = deck.clone();
+            local_deck.shuffle();
+            let mut runout = [Card::default(); 5];
+            let mut available = local_deck.into_iter().filter(|card| dead & card.mask() == 0);
+            runout.iter_mut().for_each(|slot| *slot = *available.next().unwrap());
+            black_box(runout)
+        })
+    });
+
+    group.bench_function("partial_fisher_yates", |b| {
+        let mut deck = Deck::new();
+        let mut rng = fastrand::Rng::new();
+        b.iter(|| {
+            let mut runout = [Card::default(); 5];
+            deck.deal_random(&mut rng, dead, &mut runout);
+            black_box(runout)
+        })
+    });
+
+    group.finish();
+}
+
+fn benchmark_monte_carlo_throughput(c: &mut Criterion) {
+
+    let lookup_path = std::env::var("LOOKUP_PATH").unwrap_or("data/lookup_table.bin".to_string());
+    let lookup_table = match load_lookup_table(Path::new(&lookup_path)) {
+        Ok(table) => table,
+        Err(e) => {
+            eprintln!("Failed to load lookup table: {}", e);
+            return;
+        }
+    };
+
+    const ITERATIONS: u64 = 100_000;
+    let mut group = c.benchmark_group("monte_carlo_throughput");
+    group.sample_size(20);
+    group.throughput(Throughput::Elements(ITERATIONS));
+
+    for (name, num_cards) in [("preflop_2p", 0), ("flop_2p", 3), ("turn_2p", 4)] {
+        let board = generate_random_board(num_cards);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let params = EquityParams {
+                    ranges: fixed_ranges(2),
+                    board: board.clone(),
+                    lookup: &lookup_table,
+                    reporter: None,
+                    seed: Some(0),
+                    cancel: None,
+                };
+                black_box(equity_monte_carlo(params, Some(ITERATIONS)).unwrap())
+            })
+        });
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     benchmark_monte_carlo,
-    benchmark_enumerate
+    benchmark_enumerate,
+    benchmark_stratified,
+    benchmark_runout_sampling,
+    benchmark_monte_carlo_throughput
 );
 criterion_main!(benches);
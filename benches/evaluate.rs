@@ -1,5 +1,5 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use poker::{prelude::*, evaluate::{rank_hand_bits, rank_hand_senzee, rank_hand_2p2, load_lookup_table}};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use poker::{prelude::*, evaluate::{rank_hand_bits, rank_hand_senzee, rank_hand_2p2, rank_hand_compact, eval_7_2p2, eval_7_2p2_batch, rank_hands_bits_batch, load_lookup_table, BoardState, HandRank}};
 use std::path::Path;
 
 fn generate_random_hand(size: usize) -> Vec<Card> {
@@ -78,10 +78,93 @@ fn benchmark_two_plus_two(c: &mut Criterion) {
     group.finish();
 }
 
+fn benchmark_compact(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compact");
+    
+    group.bench_function("5-card", |b| {
+        b.iter_with_setup(
+            || generate_random_hand(5),
+            |hand| black_box(rank_hand_compact(&hand).unwrap())
+        )
+    });
+    
+    group.bench_function("7-card", |b| {
+        b.iter_with_setup(
+            || generate_random_hand(7),
+            |hand| black_box(rank_hand_compact(&hand).unwrap())
+        )
+    });
+    
+    group.finish();
+}
+
+// Every hand on one board, one at a time against the batch evaluators.
+fn benchmark_batch(c: &mut Criterion) {
+
+    let lookup_path = std::env::var("LOOKUP_PATH").unwrap_or("data/lookup_table.bin".to_string());
+    let lookup_table = match load_lookup_table(Path::new(&lookup_path)) {
+        Ok(table) => table,
+        Err(e) => {
+            eprintln!("Failed to load lookup table: {}", e);
+            return;
+        }
+    };
+
+    let mut deck = Deck::new();
+    deck.shuffle();
+    let board = deck.pop_n(5);
+    let cards = deck.as_ref().to_vec();
+    let hands = cards.iter().enumerate()
+        .flat_map(|(i, &a)| cards[i + 1..].iter().map(move |&b| Hand(a, b)))
+        .collect::<Vec<Hand>>();
+
+    let mut group = c.benchmark_group("batch");
+    group.throughput(Throughput::Elements(hands.len() as u64));
+
+    group.bench_function("two_plus_two single", |b| {
+        let mut hand = [Card::default(); 7];
+        hand[2..].copy_from_slice(&board);
+        b.iter(|| {
+            for h in &hands {
+                hand[0] = h.0;
+                hand[1] = h.1;
+                black_box(eval_7_2p2(&hand, &lookup_table));
+            }
+        })
+    });
+
+    group.bench_function("two_plus_two batch", |b| {
+        let mut ranks = vec![0; hands.len()];
+        let state = BoardState::new(&board, &lookup_table);
+        b.iter(|| eval_7_2p2_batch(&state, &hands, &lookup_table, black_box(&mut ranks)))
+    });
+
+    group.bench_function("bits single", |b| {
+        let mut hand = [Card::default(); 7];
+        hand[2..].copy_from_slice(&board);
+        b.iter(|| {
+            for h in &hands {
+                hand[0] = h.0;
+                hand[1] = h.1;
+                black_box(rank_hand_bits(&hand).unwrap());
+            }
+        })
+    });
+
+    group.bench_function("bits batch", |b| {
+        let mut ranks = vec![HandRank::HighCard(0); hands.len()];
+        b.iter(|| rank_hands_bits_batch(&board, &hands, black_box(&mut ranks)))
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     benchmark_naive,
     benchmark_senzee,
-    benchmark_two_plus_two
+    benchmark_two_plus_two,
+    benchmark_compact,
+    benchmark_batch
 );
 criterion_main!(benches);
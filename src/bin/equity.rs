@@ -1,83 +1,507 @@
+use std::{fs::File, io::{BufWriter, Write}, path::Path, time::Duration};
 use anyhow::{Result, Context};
-use clap::Parser;
+use prettytable::{Table, Row, Cell};
+use clap::{Parser, Subcommand};
 use indicatif::{HumanCount, ProgressBar as IndicatifProgressBar, ProgressStyle};
-use poker::{equity::{equity_enumerate, equity_monte_carlo, EquityParams, ProgressReporter}, prelude::*};
+use poker::{error::Error, history::{parse_histories, read_ohh, write_ohh, HandHistory, SessionReport}, ev::{ev_all_in, EvSpot, Rake}, icm::{icm_call, icm_equities, IcmSpot, MAX_ICM_PLAYERS}, equity::{class_grid, equity_distribution, PreflopEquities, calculate_equity, estimate_enumeration_time, flop_equities, river_analysis, runout_equities, weighted_flop_equities, write_flop_csv, CancelToken, EquityMethod, EquityParams, EquitySnapshot, ProgressReporter}, prelude::*, solver::{position_name, push_fold, range_share, PushFoldParams}};
 
 #[derive(Debug, Parser)]
 #[command(author, version)]
 #[command(about="Range vs Range equity calculator")]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
 
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(help = "String represention of ranges to compare. Eg. '22-77' 'A2s+, KQs'")]
     ranges: Vec<String>,
 
     #[arg(short, long, help = "Board cards (0-5). Eg. '8d Tc 2h', empty for no board")]
     board: Option<String>,
 
-    #[arg(short, long, help = "Path to lookup table")]
-    lookup: String,
+    #[arg(short, long, required = true, help = "Path to lookup table")]
+    lookup: Option<String>,
     
     #[arg(short, long, help = "Use Monte Carlo simulation instead of enumeration")]
     monte_carlo: bool,
+
+    #[arg(long, conflicts_with = "monte_carlo", help = "Use stratified (quasi-random) sampling instead of enumeration")]
+    stratified: bool,
+
+    #[arg(short, long, value_name = "SECONDS", conflicts_with_all = ["monte_carlo", "stratified"], help = "Time budget in seconds, enumerates if estimated to finish in time, otherwise samples until it runs out")]
+    time: Option<f64>,
     
     #[arg(short, long, help = "Number of iterations for Monte Carlo simulation (default: run until SIGINT)")]
     iterations: Option<u64>,
+
+    #[arg(short, long, help = "Seed for Monte Carlo simulation, the same seed and thread count give identical results")]
+    seed: Option<u64>,
+
+    #[arg(long, conflicts_with_all = ["monte_carlo", "stratified", "time"], help = "Show each range's equity for every turn card on a flop, or every river card on a turn")]
+    runouts: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    #[command(about = "Enumerate every canonical flop and write each range's equity and the flop's texture as CSV")]
+    Flops {
+        #[arg(help = "Suit symmetric ranges to compare. Eg. '22-77' 'A2s+, KQs'")]
+        ranges: Vec<String>,
+
+        #[arg(short, long, help = "Path to lookup table")]
+        lookup: String,
+
+        #[arg(short, long, help = "CSV file to write, stdout if not given")]
+        output: Option<String>,
+    },
+
+    #[command(about = "Nash push/fold ranges for short stacks, printed as 13x13 grids")]
+    Pushfold {
+        #[arg(long, value_delimiter = ',', required = true, help = "Stacks in big blinds in order of action, the blinds last. Eg. '10,12,8'")]
+        stacks: Vec<f64>,
+
+        #[arg(long, default_value_t = 0.5, help = "Small blind in big blinds")]
+        small_blind: f64,
+
+        #[arg(long, default_value_t = 0.0, help = "Ante per player in big blinds")]
+        ante: f64,
+
+        #[arg(short, long, default_value_t = 1000, help = "Fictitious play iterations")]
+        iterations: u32,
+
+        #[arg(long, default_value = "./data/preflop_equities.bin", help = "Preflop equity table, computed and saved if missing")]
+        table: String,
+
+        #[arg(short, long, help = "Path to lookup table, needed to compute the preflop equity table")]
+        lookup: Option<String>,
+    },
+
+    #[command(about = "Tournament equity of each stack, or whether to call an all-in by tournament equity")]
+    Icm {
+        #[arg(help = "Hero's range then the all-in villain's, leave out for the stacks' equities only")]
+        ranges: Vec<String>,
+
+        #[arg(long, value_delimiter = ',', required = true, help = "Chips behind for each player, after what they put in this hand")]
+        stacks: Vec<f64>,
+
+        #[arg(long, value_delimiter = ',', required = true, help = "Prize for each place, first place first")]
+        payouts: Vec<f64>,
+
+        #[arg(long, value_delimiter = ',', help = "Chips each player put in this hand, blinds included")]
+        invested: Vec<f64>,
+
+        #[arg(long, default_value_t = 0, help = "Player deciding whether to call")]
+        hero: usize,
+
+        #[arg(long, default_value_t = 1, help = "Player whose bet hero faces")]
+        villain: usize,
+
+        #[arg(short, long, help = "Board cards (0-5). Eg. '8d Tc 2h', empty for no board")]
+        board: Option<String>,
+
+        #[arg(short, long, help = "Path to lookup table, needed with ranges")]
+        lookup: Option<String>,
+    },
+
+    #[command(about = "EV of calling a bet or jamming heads-up, against folding")]
+    Ev {
+        #[arg(help = "Hero's range then the villain's")]
+        ranges: Vec<String>,
+
+        #[arg(short, long, help = "Chips in the middle before hero acts, any bet hero faces included")]
+        pot: f64,
+
+        #[arg(long, help = "Chips hero puts in")]
+        bet: f64,
+
+        #[arg(long, default_value_t = 0.0, help = "Chips the villain puts in to call a jam, 0 when hero is calling")]
+        call: f64,
+
+        #[arg(long, default_value_t = 0.0, help = "Percentage of each pot taken as rake")]
+        rake: f64,
+
+        #[arg(long, help = "Most rake taken from a pot")]
+        rake_cap: Option<f64>,
+
+        #[arg(short, long, default_value_t = 0.0, help = "Percentage of the time the villain folds to hero's bet")]
+        fold: f64,
+
+        #[arg(short, long, help = "Board cards (0-5). Eg. '8d Tc 2h', empty for no board")]
+        board: Option<String>,
+
+        #[arg(short, long, help = "Path to lookup table")]
+        lookup: String,
+    },
+
+    #[command(about = "Value bets and bluffs of a betting range against a calling range on the river")]
+    River {
+        #[arg(help = "Betting range then calling range")]
+        ranges: Vec<String>,
+
+        #[arg(short, long, help = "Full board. Eg. '8d Tc 2h 5s Ac'")]
+        board: String,
+
+        #[arg(long, default_value_t = 75.0, help = "Bet size as a percentage of the pot")]
+        bet: f64,
+
+        #[arg(short, long, help = "Path to lookup table")]
+        lookup: String,
+    },
+
+    #[command(about = "Compare ranges by how their combos' equities are spread, and by nut combos")]
+    Distribution {
+        #[arg(help = "Ranges to compare. Eg. '22-77' 'A2s+, KQs'")]
+        ranges: Vec<String>,
+
+        #[arg(short, long, help = "Flop, turn or river board. Eg. '8d Tc 2h'")]
+        board: String,
+
+        #[arg(short, long, default_value_t = 3, help = "Number of strongest hand values on the board counted as the nuts")]
+        nuts: usize,
+
+        #[arg(short, long, help = "CSV file to write each range's equity CDF to")]
+        output: Option<String>,
+
+        #[arg(short, long, help = "Path to lookup table")]
+        lookup: String,
+    },
+
+    #[command(about = "Replay PokerStars hand histories and compare each player's winnings with their all-in adjusted EV")]
+    Replay {
+        #[arg(required = true, help = "Hand history files, PokerStars text or Open Hand History JSON (.json, .ohh)")]
+        files: Vec<String>,
+
+        #[arg(short, long, help = "Path to lookup table")]
+        lookup: String,
+    },
+
+    #[command(about = "Convert PokerStars hand histories to Open Hand History JSON")]
+    Ohh {
+        #[arg(required = true, help = "Hand history files")]
+        files: Vec<String>,
+
+        #[arg(short, long, help = "File to write the hands to, stdout if not given")]
+        output: Option<String>,
+    },
 }
 
 fn main() -> Result<()> {
 
     let args = Args::parse();
-    let lookup_path = args.lookup;
-    let lookup = load_lookup_table(&lookup_path)?;
-
-    let mut ranges = Vec::new();
-    if args.ranges.len() < 2 || args.ranges.len() > 8 {
-        return Err(anyhow::anyhow!("Number of ranges must be between 2 and 8"));
-    }
-    for r in args.ranges.iter() {
-        let range = Range::from_str(r).context("Failed to parse range")?;
-        ranges.push(range);
+    match args.command {
+        Some(Command::Flops { ranges, lookup, output }) => return flops(ranges, lookup, output),
+        Some(Command::Pushfold { stacks, small_blind, ante, iterations, table, lookup }) => {
+            return pushfold(stacks, small_blind, ante, iterations, table, lookup);
+        },
+        Some(Command::Icm { ranges, stacks, payouts, invested, hero, villain, board, lookup }) => {
+            let spot = IcmSpot { stacks, invested, payouts, hero, villain };
+            return icm(ranges, spot, board, lookup);
+        },
+        Some(Command::Ev { ranges, pot, bet, call, rake, rake_cap, fold, board, lookup }) => {
+            let spot = EvSpot { pot, bet, call, rake: Rake { rate: rake / 100.0, cap: rake_cap } };
+            return ev(ranges, spot, fold / 100.0, board, lookup);
+        },
+        Some(Command::River { ranges, board, bet, lookup }) => return river(ranges, board, bet / 100.0, lookup),
+        Some(Command::Distribution { ranges, board, nuts, output, lookup }) => return distribution(ranges, board, nuts, output, lookup),
+        Some(Command::Replay { files, lookup }) => return replay(files, lookup),
+        Some(Command::Ohh { files, output }) => return ohh(files, output),
+        None => {},
     }
 
+    let lookup_path = args.lookup.expect("lookup is required without a subcommand");
+    let lookup = load_lookup_table(&lookup_path)?;
+    let ranges = parse_ranges(&args.ranges)?;
+
     let board = if let Some(b) = args.board {
         Board::from_str(&b).context("Failed to parse board")?
     } else {
         Board::default()
     };
     
+    let method = if let Some(seconds) = args.time {
+        EquityMethod::Auto(Duration::from_secs_f64(seconds))
+    } else if args.stratified {
+        EquityMethod::Stratified(args.iterations)
+    } else if args.monte_carlo {
+        EquityMethod::MonteCarlo(args.iterations)
+    } else {
+        EquityMethod::Enumerate
+    };
+
     let num_runouts = board.num_runouts();
+    if args.runouts {
+        println!("Enumerating {} runouts", HumanCount(num_runouts));
+        let progress_bar = ProgressBar::new(Some(num_runouts));
+        let params = EquityParams {
+            ranges,
+            board,
+            lookup: &lookup,
+            reporter: Some(&progress_bar),
+            seed: None,
+            cancel: None,
+        };
+        let runouts = runout_equities(params).context("Failed to calculate runout equities")?;
+        progress_bar.finish();
+        runouts.print(&args.ranges);
+        return Ok(());
+    }
+
+    let enumerating = match method {
+        EquityMethod::Enumerate => true,
+        EquityMethod::Auto(budget) => estimate_enumeration_time(&ranges, &board) <= budget,
+        _ => false,
+    };
     let progress_bar = {
-        if args.monte_carlo {
+        if enumerating {
+            println!("Enumerating {} runouts", HumanCount(num_runouts));
+            Box::new(ProgressBar::new(Some(num_runouts)))
+        } else if let EquityMethod::Auto(budget) = method {
+            println!("Enumeration estimated to take too long, sampling for {:.1?}", budget);
+            Box::new(ProgressBar::new(None))
+        } else {
+            let name = if args.stratified { "Stratified sampling" } else { "Monte Carlo simulation" };
             if let Some(iterations) = args.iterations {
-                println!("Monte Carlo simulation, iterating {} games, sampling 1 matchup per iteration", HumanCount(iterations));
+                println!("{}, iterating {} games, sampling 1 matchup per iteration", name, HumanCount(iterations));
             } else{
-                println!("Monte Carlo simulation, iterating until SIGINT, sampling 1 matchup per iteration");
+                println!("{}, iterating until SIGINT, sampling 1 matchup per iteration", name);
             }
             Box::new(ProgressBar::new(args.iterations))
-        } else {
-            println!("Enumerating {} runouts", HumanCount(num_runouts));
-            Box::new(ProgressBar::new(Some(num_runouts)))
         }
     };
 
+    // Ctrl-C stops the calculation early and prints the results so far.
+    let cancel = CancelToken::new();
+    signal_hook::flag::register(signal_hook::consts::SIGINT, cancel.flag())?;
+
     let params = EquityParams {
         ranges,
         board,
         lookup: &lookup,
         reporter: Some(progress_bar.as_ref() as &dyn ProgressReporter),
+        seed: args.seed,
+        cancel: Some(&cancel),
     };
 
-    let results = if args.monte_carlo {
-        equity_monte_carlo(params, args.iterations)
-    } else {
-        equity_enumerate(params)
-    }.context("Failed to calculate equity")?;
+    let results = calculate_equity(params, method).context("Failed to calculate equity")?;
     
     progress_bar.finish();
+    if cancel.is_cancelled() && enumerating {
+        println!("Interrupted, results only cover the runouts enumerated so far");
+    }
     results.print(&args.ranges);
     Ok(())
 }
 
+fn parse_ranges(range_str: &[String]) -> Result<Vec<Range>> {
+    if range_str.len() < 2 || range_str.len() > 8 {
+        return Err(anyhow::anyhow!("Number of ranges must be between 2 and 8"));
+    }
+    range_str.iter()
+        .map(|r| Range::from_str(r).context("Failed to parse range"))
+        .collect()
+}
+
+fn flops(range_str: Vec<String>, lookup_path: String, output: Option<String>) -> Result<()> {
+    let lookup = load_lookup_table(&lookup_path)?;
+    let ranges = parse_ranges(&range_str)?;
+
+    let progress_bar = ProgressBar::new(Some(1755));
+    let flops = flop_equities(&ranges, &lookup, Some(&progress_bar)).context("Failed to calculate flop equities")?;
+    progress_bar.finish();
+
+    let mut writer: Box<dyn Write> = match &output {
+        Some(path) => Box::new(BufWriter::new(File::create(path).context("Failed to create output file")?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    write_flop_csv(&flops, &range_str, &mut writer).context("Failed to write CSV")?;
+    writer.flush()?;
+
+    // Keep stdout to the CSV when no file is given.
+    let equities = weighted_flop_equities(&flops);
+    for (range, equity) in range_str.iter().zip(equities) {
+        eprintln!("{}: {:.2}% over all flops", range, equity);
+    }
+    Ok(())
+}
+
+fn pushfold(stacks: Vec<f64>, small_blind: f64, ante: f64, iterations: u32, table: String, lookup: Option<String>) -> Result<()> {
+    let equities = if Path::new(&table).exists() {
+        PreflopEquities::load(&table)?
+    } else {
+        let lookup_path = lookup.context("No preflop equity table, pass --lookup to compute one")?;
+        let lookup = load_lookup_table(&lookup_path)?;
+        println!("Computing preflop equity table, enumerating each pair of starting hands");
+        let progress_bar = ProgressBar::new(Some(169 * 168 / 2));
+        let equities = PreflopEquities::compute(&lookup, Some(&progress_bar))?;
+        progress_bar.finish();
+        equities.save(&table)?;
+        println!("Saved preflop equity table to {}", table);
+        equities
+    };
+
+    let num_players = stacks.len();
+    let solution = push_fold(PushFoldParams {
+        stacks,
+        small_blind,
+        big_blind: 1.0,
+        ante,
+        equities: &equities,
+        iterations,
+    })?;
+
+    for jammer in 0..num_players - 1 {
+        let jams = &solution.jam[jammer];
+        println!("{} jam ({:.1}%)", position_name(jammer, num_players), range_share(jams) * 100.0);
+        println!("{}", class_grid(jams));
+        for caller in (jammer + 1)..num_players {
+            let calls = &solution.call[jammer][caller];
+            println!(
+                "{} call vs {} ({:.1}%)",
+                position_name(caller, num_players), position_name(jammer, num_players), range_share(calls) * 100.0,
+            );
+            println!("{}", class_grid(calls));
+        }
+    }
+    Ok(())
+}
+
+fn icm(range_str: Vec<String>, mut spot: IcmSpot, board: Option<String>, lookup: Option<String>) -> Result<()> {
+    if spot.stacks.len() > MAX_ICM_PLAYERS {
+        return Err(Error::InvalidIcmSpot("ICM is limited to 20 players").into());
+    }
+    if !spot.invested.is_empty() && spot.invested.len() != spot.stacks.len() {
+        return Err(Error::InvalidIcmSpot("a stack and an investment are needed for each player").into());
+    }
+    if range_str.is_empty() {
+        let mut stacks = spot.stacks.clone();
+        if !spot.invested.is_empty() {
+            stacks.iter_mut().zip(&spot.invested).for_each(|(stack, invested)| *stack += invested);
+        }
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![Cell::new("Player"), Cell::new("Stack"), Cell::new("Equity")]));
+        for (player, (stack, equity)) in stacks.iter().zip(icm_equities(&stacks, &spot.payouts)).enumerate() {
+            table.add_row(Row::new(vec![
+                Cell::new(&player.to_string()),
+                Cell::new(&format!("{}", stack)),
+                Cell::new(&format!("{:.2}", equity)),
+            ]));
+        }
+        table.printstd();
+        return Ok(());
+    }
+
+    if range_str.len() != 2 {
+        return Err(anyhow::anyhow!("Pass hero's range and the villain's"));
+    }
+    if spot.invested.is_empty() {
+        spot.invested = vec![0.0; spot.stacks.len()];
+    }
+    let lookup = load_lookup_table(lookup.context("Pass --lookup to enumerate the ranges")?)?;
+    let ranges = parse_ranges(&range_str)?;
+    let board = board.map_or(Ok(Board::default()), |b| Board::from_str(&b)).context("Failed to parse board")?;
+
+    let decision = icm_call(&spot, [ranges[0].clone(), ranges[1].clone()], board, &lookup)?;
+    println!("Equity:           {:.2}%", decision.equity * 100.0);
+    println!("Required (chips): {:.2}%", decision.chip_required * 100.0);
+    println!("Required (ICM):   {:.2}%", decision.required_equity * 100.0);
+    println!("Call:             {:.2}", decision.call_ev);
+    println!("Fold:             {:.2}", decision.fold_ev);
+    println!("{}", if decision.should_call() { "Call" } else { "Fold" });
+    Ok(())
+}
+
+fn ev(range_str: Vec<String>, spot: EvSpot, fold_frequency: f64, board: Option<String>, lookup: String) -> Result<()> {
+    if range_str.len() != 2 {
+        return Err(anyhow::anyhow!("Pass hero's range and the villain's"));
+    }
+    let lookup = load_lookup_table(lookup)?;
+    let ranges = parse_ranges(&range_str)?;
+    let board = board.map_or(Ok(Board::default()), |b| Board::from_str(&b)).context("Failed to parse board")?;
+
+    let decision = ev_all_in(&spot, [ranges[0].clone(), ranges[1].clone()], board, &lookup, fold_frequency)?;
+    println!("Equity:            {:.2}%", decision.equity * 100.0);
+    println!("Break-even equity: {:.2}%", decision.break_even_equity * 100.0);
+    println!("Showdown EV:       {:.2}", decision.showdown_ev);
+    if spot.call > 0.0 {
+        println!("Fold equity:       {:.2}", decision.fold_equity);
+        println!("Break-even folds:  {:.2}%", decision.break_even_fold * 100.0);
+    }
+    println!("EV:                {:.2}", decision.ev);
+    println!("Fold EV:           0.00");
+    println!("{}", if decision.profitable() { if spot.call > 0.0 { "Jam" } else { "Call" } } else { "Fold" });
+    Ok(())
+}
+
+fn river(range_str: Vec<String>, board: String, bet: f64, lookup: String) -> Result<()> {
+    if range_str.len() != 2 {
+        return Err(anyhow::anyhow!("Pass the betting range and the calling range"));
+    }
+    if !(bet.is_finite() && bet > 0.0) {
+        return Err(anyhow::anyhow!("Bet size must be positive"));
+    }
+    let lookup = load_lookup_table(lookup)?;
+    let ranges = parse_ranges(&range_str)?;
+    let board = Board::from_str(&board).context("Failed to parse board")?;
+
+    river_analysis(&ranges[0], &ranges[1], &board, bet, &lookup)?.print();
+    Ok(())
+}
+
+fn distribution(range_str: Vec<String>, board: String, nuts: usize, output: Option<String>, lookup: String) -> Result<()> {
+    if nuts == 0 {
+        return Err(anyhow::anyhow!("Count at least one hand value as the nuts"));
+    }
+    let lookup = load_lookup_table(lookup)?;
+    let ranges = parse_ranges(&range_str)?;
+    let board = Board::from_str(&board).context("Failed to parse board")?;
+
+    let params = EquityParams { ranges, board, lookup: &lookup, reporter: None, seed: None, cancel: None };
+    let distribution = equity_distribution(params, nuts).context("Failed to calculate equity distribution")?;
+    distribution.print(&range_str);
+
+    if let Some(path) = output {
+        let mut writer = BufWriter::new(File::create(&path).context("Failed to create output file")?);
+        distribution.write_csv(&range_str, &mut writer).context("Failed to write CSV")?;
+        println!("Wrote equity CDF to {}", path);
+    }
+    Ok(())
+}
+
+// Reads hands from PokerStars text files, or Open Hand History files by their extension.
+fn read_histories(files: &[String]) -> Result<Vec<HandHistory>> {
+    let mut hands = Vec::new();
+    for path in files {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+        let parsed = if path.ends_with(".json") || path.ends_with(".ohh") { read_ohh(&text) } else { parse_histories(&text) };
+        hands.extend(parsed.with_context(|| format!("Failed to parse {}", path))?);
+    }
+    Ok(hands)
+}
+
+fn replay(files: Vec<String>, lookup: String) -> Result<()> {
+    let lookup = load_lookup_table(lookup)?;
+    let hands = read_histories(&files)?;
+
+    let report = SessionReport::replay(&hands, &lookup).context("Failed to replay hands")?;
+    println!("Replayed {} hands, {} of them all-in before the river", report.hands.len(), report.hands.iter().filter(|hand| hand.all_in.contains(&true)).count());
+    report.print();
+    Ok(())
+}
+
+fn ohh(files: Vec<String>, output: Option<String>) -> Result<()> {
+    let hands = read_histories(&files)?;
+    match output {
+        Some(path) => {
+            let mut writer = BufWriter::new(File::create(&path).context("Failed to create output file")?);
+            write_ohh(&hands, &mut writer).context("Failed to write hands")?;
+            println!("Wrote {} hands to {}", hands.len(), path);
+        },
+        None => write_ohh(&hands, &mut std::io::stdout().lock()).context("Failed to write hands")?,
+    }
+    Ok(())
+}
+
 struct ProgressBar {
     bar: IndicatifProgressBar,
     
@@ -89,7 +513,7 @@ impl ProgressBar {
             Some(n) => {
                 let bar = IndicatifProgressBar::new(n);
                 bar.set_style(ProgressStyle::default_bar()
-                    .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {human_pos}/{human_len}")
+                    .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {human_pos}/{human_len} {msg}")
                     .unwrap()
                     .progress_chars("#>-"));
                 bar
@@ -97,7 +521,7 @@ impl ProgressBar {
             None => {
                 let bar = IndicatifProgressBar::new_spinner();
                 bar.set_style(ProgressStyle::default_spinner()
-                    .template("{spinner:.green} [{elapsed_precise}] {human_pos} games")
+                    .template("{spinner:.green} [{elapsed_precise}] {human_pos} games {msg}")
                     .unwrap());
                 bar
             }
@@ -114,4 +538,13 @@ impl ProgressReporter for ProgressBar {
     fn board_complete(&self) {
         self.bar.inc(1);
     }
+
+    fn snapshot(&self, snapshot: &EquitySnapshot) {
+        let equities = snapshot.results.equities()
+            .iter()
+            .map(|e| format!("{:.2}%", e))
+            .collect::<Vec<String>>()
+            .join(" | ");
+        self.bar.set_message(format!("{} ({}/s)", equities, HumanCount(snapshot.throughput() as u64)));
+    }
 }
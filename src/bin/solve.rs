@@ -0,0 +1,102 @@
+use std::time::Instant;
+use anyhow::{Result, Context};
+use clap::Parser;
+use indicatif::HumanCount;
+use poker::{solver::{solve, BetSizes, GameTree, SolverParams, TreeConfig}, prelude::*};
+
+#[derive(Debug, Parser)]
+#[command(author, version)]
+#[command(about="Heads-up postflop solver")]
+struct Args {
+
+    #[arg(help = "Out of position range then in position range. Eg. 'QQ+, AKs' 'TT-JJ, AQs'")]
+    ranges: Vec<String>,
+
+    #[arg(short, long, help = "Board cards (3-5). Eg. '8d Tc 2h'")]
+    board: String,
+
+    #[arg(short, long, help = "Path to lookup table")]
+    lookup: String,
+
+    #[arg(short, long, default_value_t = 100.0, help = "Pot at the start of the street")]
+    pot: f64,
+
+    #[arg(short, long, default_value_t = 100.0, help = "Effective stack behind")]
+    stack: f64,
+
+    #[arg(long, value_delimiter = ',', default_values_t = [33.0, 75.0], help = "Bet sizes as percentages of the pot")]
+    bets: Vec<f64>,
+
+    #[arg(long, value_delimiter = ',', default_values_t = [100.0], help = "Raise sizes as percentages of the pot after calling")]
+    raises: Vec<f64>,
+
+    #[arg(long, default_value_t = 3, help = "Raises allowed on a street after the first bet")]
+    raise_cap: u32,
+
+    #[arg(long, default_value_t = 67.0, help = "Bets putting in at least this percentage of the stack go all-in instead")]
+    all_in_threshold: f64,
+
+    #[arg(long, help = "Allow out of position to lead into the last street's aggressor")]
+    donk: bool,
+
+    #[arg(long, default_value_t = 50_000_000, help = "Refuse to build trees with more nodes than this")]
+    max_nodes: u64,
+
+    #[arg(short, long, default_value_t = 1000, help = "Maximum number of iterations")]
+    iterations: u32,
+
+    #[arg(short, long, default_value_t = 0.5, help = "Stop once exploitability is below this percentage of the pot")]
+    exploitability: f64,
+}
+
+fn main() -> Result<()> {
+
+    let args = Args::parse();
+    let lookup = load_lookup_table(&args.lookup)?;
+
+    if args.ranges.len() != 2 {
+        return Err(anyhow::anyhow!("Solving needs exactly 2 ranges"));
+    }
+    let oop = Range::from_str(&args.ranges[0]).context("Failed to parse range")?;
+    let ip = Range::from_str(&args.ranges[1]).context("Failed to parse range")?;
+    let board = Board::from_str(&args.board).context("Failed to parse board")?;
+
+    let tree = TreeConfig {
+        raise_cap: args.raise_cap,
+        all_in_threshold: args.all_in_threshold / 100.0,
+        donk_bets: args.donk,
+        max_nodes: args.max_nodes,
+        ..TreeConfig::new(BetSizes {
+            bet:   args.bets.iter().map(|size| size / 100.0).collect(),
+            raise: args.raises.iter().map(|size| size / 100.0).collect(),
+        })
+    };
+    let count = GameTree::estimate(&board, args.pot, args.stack, &tree)?;
+    println!(
+        "Building a tree of {} nodes, {} of them actions",
+        HumanCount(count.total()), HumanCount(count.action),
+    );
+
+    let params = SolverParams {
+        ranges: [oop, ip],
+        board,
+        pot: args.pot,
+        stack: args.stack,
+        tree,
+        lookup: &lookup,
+        iterations: args.iterations,
+        target_exploitability: args.exploitability,
+    };
+
+    let start = Instant::now();
+    let solution = solve(params).context("Failed to solve")?;
+    println!(
+        "Solved in {:.1?}, {} iterations, exploitability {:.3}% of the pot",
+        start.elapsed(), solution.iterations, solution.exploitability,
+    );
+
+    let ev = solution.ev();
+    println!("EV: {} {:.2}, {} {:.2}", args.ranges[0], ev[0], args.ranges[1], ev[1]);
+    solution.root_strategy().print();
+    Ok(())
+}
@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+use crate::{card::Card, hand::Hand, range::Range};
+use super::Board;
+
+// Relabelling of suits, card suit s becomes suit self.0[s].  Flops that are the same up to a
+// relabelling are strategically identical.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SuitIsomorphism(pub [u8; 4]);
+
+impl Default for SuitIsomorphism {
+    fn default() -> Self {
+        Self([0, 1, 2, 3])
+    }
+}
+
+impl SuitIsomorphism {
+    pub fn card(&self, card: Card) -> Card {
+        Card(card.0 & !3 | self.0[card.suit_u8() as usize])
+    }
+
+    pub fn hand(&self, hand: Hand) -> Hand {
+        Hand(self.card(hand.0), self.card(hand.1))
+    }
+
+    pub fn range(&self, range: &Range) -> Range {
+        let mut mapped = Range::default();
+        for idx in 0..1326 {
+            if range[idx] {
+                mapped.set_hand(&self.hand(Hand::from_idx(idx)), true);
+            }
+        }
+        mapped
+    }
+
+    pub fn inverse(&self) -> Self {
+        let mut inverse = [0; 4];
+        for (suit, &mapped) in self.0.iter().enumerate() {
+            inverse[mapped as usize] = suit as u8;
+        }
+        Self(inverse)
+    }
+
+    // The 24 relabellings, the identity first.
+    pub fn all() -> impl Iterator<Item = SuitIsomorphism> {
+        (0..24).map(|mut n| {
+            // Lehmer code of the permutation.
+            let mut suits = vec![0, 1, 2, 3];
+            let mut perm = [0; 4];
+            for (i, slot) in perm.iter_mut().enumerate() {
+                let radix = 4 - i;
+                *slot = suits.remove(n % radix);
+                n /= radix;
+            }
+            SuitIsomorphism(perm)
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CanonicalFlop {
+    pub board:  Board,
+    // Number of the 22,100 flops isomorphic to this one.
+    pub weight: u32,
+}
+
+impl Board {
+    // The flop's canonical representative with the relabelling that maps the flop to it.  Of the
+    // relabellings, the one giving the highest cards is canonical, so the suit of the top card
+    // becomes spades.  Where the flop leaves suits interchangeable more than one relabelling maps
+    // to the representative, this returns the first.  None unless the board is just a flop.
+    pub fn canonical_flop(&self) -> Option<(Board, SuitIsomorphism)> {
+        if !self.is_flop_dealt() || self.is_turn_dealt() {
+            return None;
+        }
+        let (flop, isomorphism) = canonical(self.flop);
+        Some((Board::from_vec(flop.to_vec()).unwrap(), isomorphism))
+    }
+}
+
+// The 1,755 canonical flops, in descending order of their cards.
+pub fn canonical_flops() -> Vec<CanonicalFlop> {
+    let mut flops = BTreeMap::<[u8; 3], u32>::new();
+    for a in 0..52 {
+        for b in (a + 1)..52 {
+            for c in (b + 1)..52 {
+                let (flop, _) = canonical([Card(a), Card(b), Card(c)]);
+                *flops.entry(flop.map(|card| card.0)).or_default() += 1;
+            }
+        }
+    }
+
+    flops.into_iter().rev().map(|(flop, weight)| CanonicalFlop {
+        board: Board::from_vec(flop.map(Card).to_vec()).unwrap(),
+        weight,
+    }).collect()
+}
+
+fn canonical(flop: [Card; 3]) -> ([Card; 3], SuitIsomorphism) {
+    SuitIsomorphism::all()
+        .map(|isomorphism| {
+            let mut cards = flop.map(|card| isomorphism.card(card));
+            cards.sort_unstable_by(|a, b| b.cmp(a));
+            (cards, isomorphism)
+        })
+        .fold(None, |best: Option<([Card; 3], SuitIsomorphism)>, (cards, isomorphism)| match best {
+            Some((best_cards, _)) if best_cards >= cards => best,
+            _ => Some((cards, isomorphism)),
+        })
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_flops() {
+        let flops = canonical_flops();
+        assert_eq!(flops.len(), 1755);
+        assert_eq!(flops.iter().map(|flop| flop.weight).sum::<u32>(), 22_100);
+
+        // Trips and monotone flops stand for 4, rainbow unpaired flops for 24.
+        assert_eq!(flops[0].board.as_vec(), Card::vec_from_str("As Ah Ad").unwrap());
+        assert_eq!(flops[0].weight, 4);
+        let weight = |board: &str| {
+            let canonical = Board::from_str(board).unwrap().canonical_flop().unwrap().0;
+            flops.iter().find(|flop| flop.board.flop == canonical.flop).unwrap().weight
+        };
+        assert_eq!(weight("Qh Kh Ah"), 4);
+        assert_eq!(weight("Ah Kd Qc"), 24);
+        assert_eq!(weight("Ah Kh Qc"), 12);
+    }
+
+    #[test]
+    fn test_canonical_flop() {
+        let board = Board::from_str("7d 2h 7c").unwrap();
+        let (canonical, isomorphism) = board.canonical_flop().unwrap();
+        assert_eq!(canonical.as_vec(), Card::vec_from_str("7s 7h 2d").unwrap());
+
+        // Every flop of the class maps to the same representative, with the hands following.
+        let other = Board::from_str("7h 7s 2c").unwrap();
+        let (other_canonical, other_isomorphism) = other.canonical_flop().unwrap();
+        assert_eq!(other_canonical.flop, canonical.flop);
+
+        let hand = Hand::from_str("Ad Kd").unwrap();
+        let mapped = isomorphism.hand(hand);
+        assert_eq!(mapped.0.suit_u8(), mapped.1.suit_u8());
+        assert_eq!(isomorphism.inverse().hand(mapped), hand);
+        assert_eq!(other_isomorphism.inverse().card(canonical.flop[2]), Card::from_str("2c").unwrap());
+
+        let range = Range::from_str("AKs").unwrap();
+        assert_eq!(isomorphism.range(&range).hand_combos(0).len(), 4);
+
+        assert!(Board::from_str("Ah Kd 2c 7s").unwrap().canonical_flop().is_none());
+        assert!(Board::default().canonical_flop().is_none());
+    }
+}
@@ -1,7 +1,13 @@
 use std::collections::HashSet;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 use crate::card::{Card, CardParseError};
 
+mod isomorphism;
+mod texture;
+pub use isomorphism::{canonical_flops, CanonicalFlop, SuitIsomorphism};
+pub use texture::{representative_flops, BoardTexture, Pairing, Suitedness, WeightedFlop};
+
 #[derive(Debug, Default, Clone)]
 pub struct Board {
     pub flop:  [Card; 3],
@@ -125,10 +131,34 @@ impl Board {
     }
 }
 
+// Boards serialize as the list of cards dealt, empty preflop.
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_vec().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Board::from_vec(Vec::<Card>::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_serde() {
+        let board = Board::from_str("Ah 7d 2c Ts").unwrap();
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(json, r#"["Ah","7d","2c","Ts"]"#);
+        assert_eq!(serde_json::from_str::<Board>(&json).unwrap().as_vec(), board.as_vec());
+        assert_eq!(serde_json::to_string(&Board::default()).unwrap(), "[]");
+        assert!(serde_json::from_str::<Board>(r#"["Ah","7d"]"#).is_err());
+        assert!(serde_json::from_str::<Board>(r#"["Ah","7d","Ah"]"#).is_err());
+    }
+
     #[test]
     fn test_new() {
 
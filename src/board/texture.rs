@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+use crate::card::{Card, Rank};
+use super::Board;
+
+// By the most cards of one suit.  On the turn and river monotone means 3 or more of a suit,
+// so a flush is possible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Suitedness {
+    Rainbow,
+    TwoTone,
+    Monotone,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Pairing {
+    Unpaired,
+    Paired,
+    TwoPair,
+    Trips,
+    FullHouse,
+    Quads,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoardTexture {
+    pub suitedness:          Suitedness,
+    pub pairing:             Pairing,
+    pub high_card:           Rank,
+    // Most distinct board ranks within any 5 consecutive ranks, the ace also counting low.
+    pub connectedness:       u8,
+    // Some hand makes a straight or flush with the board as it is.
+    pub straight_possible:   bool,
+    pub flush_possible:      bool,
+    // A straight or flush can still be made with cards to come.
+    pub straight_draw:       bool,
+    pub flush_draw:          bool,
+    // Rough measure in [0, 1] of how many hands connect with the board and how much the
+    // best hand can change on later streets.
+    pub wetness:             f64,
+}
+
+impl BoardTexture {
+    // None before the flop, there is no board to describe.
+    pub fn new(board: &Board) -> Option<Self> {
+        let cards = board.as_vec();
+        if cards.len() < 3 {
+            return None;
+        }
+        let to_come = cards.len() < 5;
+
+        let mut rank_counts = [0_u8; 13];
+        let mut suit_counts = [0_u8; 4];
+        for card in &cards {
+            rank_counts[card.rank_u8() as usize] += 1;
+            suit_counts[card.suit_u8() as usize] += 1;
+        }
+
+        let max_suit = *suit_counts.iter().max().unwrap();
+        let suitedness = match max_suit {
+            1 => Suitedness::Rainbow,
+            2 => Suitedness::TwoTone,
+            _ => Suitedness::Monotone,
+        };
+
+        let pairs = rank_counts.iter().filter(|&&c| c == 2).count();
+        let pairing = match (*rank_counts.iter().max().unwrap(), pairs) {
+            (4, _) => Pairing::Quads,
+            (3, 0) => Pairing::Trips,
+            (3, _) => Pairing::FullHouse,
+            (2, 1) => Pairing::Paired,
+            (2, _) => Pairing::TwoPair,
+            _ => Pairing::Unpaired,
+        };
+
+        let rank_set = rank_counts.iter().enumerate()
+            .filter(|(_, &c)| c > 0)
+            .fold(0_u32, |set, (rank, _)| set | 1 << rank);
+        let high_card = Rank::from(31 - rank_set.leading_zeros() as u8);
+        let connectedness = connectedness(rank_set);
+
+        let straight_possible = connectedness >= 3;
+        let flush_possible = max_suit >= 3;
+        let straight_draw = to_come && connectedness >= 2;
+        let flush_draw = to_come && max_suit >= 2;
+
+        let mut wetness = 0.0;
+        wetness += if flush_possible { 0.4 } else if flush_draw { 0.2 } else { 0.0 };
+        wetness += match connectedness {
+            0..=1 => 0.0,
+            2 => if to_come { 0.15 } else { 0.0 },
+            3 => 0.3,
+            _ => 0.4,
+        };
+        // More of a typical range holds broadway cards.
+        let broadway = cards.iter().filter(|card| card.rank() >= Rank::Ten).count();
+        wetness += 0.2 * broadway as f64 / cards.len() as f64;
+        if pairing != Pairing::Unpaired {
+            wetness *= 0.75;
+        }
+
+        Some(Self {
+            suitedness,
+            pairing,
+            high_card,
+            connectedness,
+            straight_possible,
+            flush_possible,
+            straight_draw,
+            flush_draw,
+            wetness: f64::min(wetness, 1.0),
+        })
+    }
+}
+
+impl Board {
+    pub fn texture(&self) -> Option<BoardTexture> {
+        BoardTexture::new(self)
+    }
+}
+
+fn connectedness(rank_set: u32) -> u8 {
+    // Ace low below the deuce.
+    let ranks = rank_set << 1 | rank_set >> 12;
+    (0..10).map(|low| (ranks >> low & 0b11111).count_ones() as u8).max().unwrap()
+}
+
+#[derive(Debug, Clone)]
+pub struct WeightedFlop {
+    pub board:  Board,
+    // Fraction of all 22,100 flops this flop stands for.
+    pub weight: f64,
+}
+
+// Rank groupings from finest to coarsest, mapping each rank to its group.
+const RANK_GROUPS: [[u8; 13]; 6] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12],
+    [0, 0, 1, 1, 2, 2, 3, 3, 4, 5, 6, 7, 8],
+    [0, 0, 0, 1, 1, 1, 2, 2, 3, 3, 4, 5, 6],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 3, 4],
+    [0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1],
+];
+
+// Picks a weighted subset of at most max_flops flops, or the coarsest there is if max_flops is
+// smaller.  Flops are grouped by suitedness, pairing, connectedness and their ranks, with ranks
+// grouped more coarsely until the groups fit, giving 1,183, 567, 338, 159, 89 or 40 flops.
+// Each group is represented by its middle flop, weighted by the number of flops in it.
+pub fn representative_flops(max_flops: usize) -> Vec<WeightedFlop> {
+    let flops = all_flops();
+
+    let mut groups = BTreeMap::new();
+    for rank_groups in RANK_GROUPS {
+        groups = group_flops(&flops, &rank_groups);
+        if groups.len() <= max_flops {
+            break;
+        }
+    }
+
+    groups.into_values().map(|mut group| {
+        group.sort_by_key(|flop| std::cmp::Reverse(sorted_ranks(flop)));
+        let flop = group[group.len() / 2];
+        WeightedFlop {
+            board:  Board::from_vec(flop.to_vec()).unwrap(),
+            weight: group.len() as f64 / flops.len() as f64,
+        }
+    }).collect()
+}
+
+type FlopKey = (Suitedness, Pairing, u8, [u8; 3]);
+
+fn group_flops(flops: &[[Card; 3]], rank_groups: &[u8; 13]) -> BTreeMap<FlopKey, Vec<[Card; 3]>> {
+    let mut groups = BTreeMap::<FlopKey, Vec<[Card; 3]>>::new();
+    for flop in flops {
+        let texture = Board::from_vec(flop.to_vec()).unwrap().texture().unwrap();
+        let ranks = sorted_ranks(flop).map(|rank| rank_groups[rank as usize]);
+        let key = (texture.suitedness, texture.pairing, texture.connectedness, ranks);
+        groups.entry(key).or_default().push(*flop);
+    }
+    groups
+}
+
+fn all_flops() -> Vec<[Card; 3]> {
+    let mut flops = Vec::with_capacity(22_100);
+    for a in 0..52 {
+        for b in (a + 1)..52 {
+            for c in (b + 1)..52 {
+                flops.push([Card(a), Card(b), Card(c)]);
+            }
+        }
+    }
+    flops
+}
+
+// Highest rank first.
+fn sorted_ranks(flop: &[Card; 3]) -> [u8; 3] {
+    let mut ranks = flop.map(|card| card.rank_u8());
+    ranks.sort_unstable_by(|a, b| b.cmp(a));
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_texture() {
+        let texture = Board::from_str("Ah Kh Qh").unwrap().texture().unwrap();
+        assert_eq!(texture.suitedness, Suitedness::Monotone);
+        assert_eq!(texture.pairing, Pairing::Unpaired);
+        assert_eq!(texture.high_card, Rank::Ace);
+        assert_eq!(texture.connectedness, 3);
+        assert!(texture.straight_possible && texture.flush_possible);
+
+        let texture = Board::from_str("7c 7d 2s").unwrap().texture().unwrap();
+        assert_eq!(texture.suitedness, Suitedness::Rainbow);
+        assert_eq!(texture.pairing, Pairing::Paired);
+        assert!(!texture.straight_possible && !texture.flush_draw);
+        assert!(texture.wetness < Board::from_str("Ah Kh Qh").unwrap().texture().unwrap().wetness);
+
+        // The wheel counts the ace low.
+        let texture = Board::from_str("Ac 2d 4s 9h").unwrap().texture().unwrap();
+        assert_eq!(texture.suitedness, Suitedness::Rainbow);
+        assert_eq!(texture.connectedness, 3);
+
+        let texture = Board::from_str("9c 9d 9s 4h 4c").unwrap().texture().unwrap();
+        assert_eq!(texture.pairing, Pairing::FullHouse);
+        assert!(!texture.straight_draw && !texture.flush_draw);
+
+        assert_eq!(Board::default().texture(), None);
+    }
+
+    #[test]
+    fn test_representative_flops() {
+        for (max_flops, len) in [(usize::MAX, 1183), (1000, 567), (184, 159), (50, 40), (1, 40)] {
+            let flops = representative_flops(max_flops);
+            assert_eq!(flops.len(), len);
+            assert!((flops.iter().map(|flop| flop.weight).sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+    }
+}
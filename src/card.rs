@@ -1,4 +1,5 @@
 use std::fmt::{Display, Debug};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -43,6 +44,16 @@ impl Suit {
             _ => Err(CardParseError::InvalidSuit(s)),
         }
     }
+
+    // Inverse of from_str, for plain text output.
+    pub fn to_char(&self) -> char {
+        match self {
+            Suit::Hearts   => 'h',
+            Suit::Diamonds => 'd',
+            Suit::Clubs    => 'c',
+            Suit::Spades   => 's',
+        }
+    }
 }
 
 impl Display for Suit {
@@ -267,6 +278,11 @@ impl Card {
         Ok(cards)
     }
 
+    // Rank and suit letter, eg. "Ah", where Display uses the suit symbol.
+    pub fn ascii(&self) -> String {
+        format!("{}{}", self.rank(), self.suit().to_char())
+    }
+
     #[inline]
     pub fn suit(&self) -> Suit {
         (self.0 & 3).into()
@@ -339,6 +355,20 @@ impl Display for Card {
     }
 }
 
+// Cards serialize as their two character notation, eg. "Ah".
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.ascii())
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Card::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
 impl Debug for Card {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if !self.is_dealt() {
@@ -353,6 +383,14 @@ impl Debug for Card {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_serde() {
+        let card = Card::from_str("Ah").unwrap();
+        assert_eq!(serde_json::to_string(&card).unwrap(), "\"Ah\"");
+        assert_eq!(serde_json::from_str::<Card>("\"tc\"").unwrap(), Card::from_str("Tc").unwrap());
+        assert!(serde_json::from_str::<Card>("\"Ax\"").is_err());
+    }
+
     #[test]
     fn test_mem_size() {
         assert!(std::mem::size_of::<Card>() == 1);
@@ -20,6 +20,31 @@ impl Deck {
         fastrand::shuffle(&mut self.0);
     }
 
+    pub fn shuffle_with(&mut self, rng: &mut fastrand::Rng) {
+        rng.shuffle(&mut self.0);
+    }
+
+    // Deals random cards not in the dead mask with a partial Fisher-Yates shuffle, only
+    // moving the cards dealt.  The deck keeps all its cards, so it can be reused without allocating.
+    // The deck must hold at least out.len() cards not in the dead mask, or this never returns.
+    pub fn deal_random(&mut self, rng: &mut fastrand::Rng, dead: u64, out: &mut [Card]) {
+        debug_assert!(
+            self.0.iter().filter(|card| dead & card.mask() == 0).count() >= out.len(),
+            "not enough live cards to deal",
+        );
+        let mut i = 0;
+        while i < out.len() {
+            let j = rng.usize(i..self.0.len());
+            let card = self.0[j];
+            if dead & card.mask() != 0 {
+                continue;
+            }
+            self.0.swap(i, j);
+            out[i] = card;
+            i += 1;
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.0.len()
     }
@@ -77,4 +102,28 @@ mod tests {
         assert!(deck[0].rank() == Rank::Two);
         assert!(deck[51].rank() == Rank::Ace);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_deal_random() {
+        let mut deck = Deck::new();
+        let mut rng = fastrand::Rng::with_seed(0);
+        let dead = 0xFFFF_u64;
+
+        for _ in 0..1000 {
+            let mut cards = [Card::default(); 5];
+            deck.deal_random(&mut rng, dead, &mut cards);
+            let mask = cards.iter().fold(0, |mask, card| mask | card.mask());
+            assert_eq!(mask.count_ones(), 5);
+            assert_eq!(mask & dead, 0);
+        }
+        assert_eq!(deck.len(), 52);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "not enough live cards")]
+    fn test_deal_random_dead_deck() {
+        let mut cards = [Card::default(); 2];
+        Deck::new().deal_random(&mut fastrand::Rng::with_seed(0), (1 << 51) - 1, &mut cards);
+    }
+}
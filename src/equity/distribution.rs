@@ -0,0 +1,129 @@
+use std::io::Write;
+use prettytable::{Table, Row, Cell};
+use crate::{evaluate::BoardState, error::Error, prelude::*};
+use super::{equity_histograms, EquityParams};
+
+// How each range's equity is spread over its combos, and how many of them hold the nuts.
+#[derive(Debug, Clone)]
+pub struct EquityDistribution {
+    // Equity of each combo of each range over the runouts, ascending.
+    pub equities:   Vec<Vec<f64>>,
+    // Combos of each range with one of the strongest hand values possible on the board.
+    pub nuts:       Vec<usize>,
+    pub nut_values: usize,
+}
+
+impl EquityDistribution {
+    pub fn combos(&self, range: usize) -> usize {
+        self.equities[range].len()
+    }
+
+    pub fn mean(&self, range: usize) -> f64 {
+        self.equities[range].iter().sum::<f64>() / self.combos(range) as f64
+    }
+
+    // Share of the range's combos with more than the given equity.
+    pub fn share_above(&self, range: usize, equity: f64) -> f64 {
+        1.0 - self.cdf(range, equity)
+    }
+
+    // Share of the range's combos with at most the given equity.
+    pub fn cdf(&self, range: usize, equity: f64) -> f64 {
+        let equities = &self.equities[range];
+        equities.partition_point(|&e| e <= equity) as f64 / equities.len() as f64
+    }
+
+    pub fn nut_share(&self, range: usize) -> f64 {
+        self.nuts[range] as f64 / self.combos(range) as f64
+    }
+
+    pub fn print(&self, range_str: &[String]) {
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![
+            Cell::new("Range"),
+            Cell::new("Combos"),
+            Cell::new("Equity"),
+            Cell::new("> 80%"),
+            Cell::new(&format!("Nuts (top {})", self.nut_values)),
+        ]));
+        for (range, name) in range_str.iter().enumerate() {
+            table.add_row(Row::new(vec![
+                Cell::new(name),
+                Cell::new(&self.combos(range).to_string()),
+                Cell::new(&format!("{:.2}%", self.mean(range) * 100.0)),
+                Cell::new(&format!("{:.1}%", self.share_above(range, 0.8) * 100.0)),
+                Cell::new(&format!("{} ({:.1}%)", self.nuts[range], self.nut_share(range) * 100.0)),
+            ]));
+        }
+        table.printstd();
+
+        let mut table = Table::new();
+        let mut header = vec![Cell::new("Equity <=")];
+        header.extend(range_str.iter().map(|range| Cell::new(range)));
+        table.add_row(Row::new(header));
+        for step in 1..=10 {
+            let equity = step as f64 / 10.0;
+            let mut row = vec![Cell::new(&format!("{:.0}%", equity * 100.0))];
+            row.extend((0..range_str.len()).map(|range| Cell::new(&format!("{:.1}%", self.cdf(range, equity) * 100.0))));
+            table.add_row(Row::new(row));
+        }
+        table.printstd();
+    }
+
+    // The CDF of each range's combo equities at every whole percent.
+    pub fn write_csv<W: Write>(&self, range_str: &[String], writer: &mut W) -> std::io::Result<()> {
+        let ranges = range_str.iter()
+            .map(|range| format!("\"{}\"", range.replace('"', "\"\"")))
+            .collect::<Vec<String>>()
+            .join(",");
+        writeln!(writer, "equity,{}", ranges)?;
+
+        for percent in 0..=100 {
+            let cdf = (0..range_str.len())
+                .map(|range| format!("{:.4}", self.cdf(range, percent as f64 / 100.0)))
+                .collect::<Vec<String>>()
+                .join(",");
+            writeln!(writer, "{},{}", percent, cdf)?;
+        }
+        Ok(())
+    }
+}
+
+// Enumerates every runout for each combo's equity, and counts the combos making one of the
+// strongest nut_values hand values on the current board.
+pub fn equity_distribution(params: EquityParams, nut_values: usize) -> Result<EquityDistribution> {
+    assert!(nut_values > 0);
+    let num_cards = params.board.as_vec().len();
+    if num_cards < 3 {
+        return Err(Error::InvalidNutBoard(num_cards));
+    }
+
+    let board = params.board.clone();
+    let lookup = params.lookup;
+    let strengths = equity_histograms(params, 1)?;
+
+    let state = BoardState::new(&board.as_vec(), lookup);
+    let rank = |hand: &Hand| state.add_card(hand.0, lookup).add_card(hand.1, lookup).rank(lookup);
+    let mut values = Range::new_from_grid(vec![true; 169]).hand_combos(board.mask())
+        .iter()
+        .map(rank)
+        .collect::<Vec<u16>>();
+    values.sort_unstable_by(|a, b| b.cmp(a));
+    values.dedup();
+    let threshold = values[(nut_values - 1).min(values.len() - 1)];
+
+    // Combos never dealt against the other ranges are left out.
+    let strengths = strengths.into_iter()
+        .map(|combos| combos.into_iter().filter(|strength| strength.runouts > 0.0).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let nuts = strengths.iter()
+        .map(|combos| combos.iter().filter(|strength| rank(&strength.hand) >= threshold).count())
+        .collect();
+    let equities = strengths.iter().map(|combos| {
+        let mut equities = combos.iter().map(|strength| strength.ehs).collect::<Vec<f64>>();
+        equities.sort_by(f64::total_cmp);
+        equities
+    }).collect();
+
+    Ok(EquityDistribution { equities, nuts, nut_values })
+}
@@ -1,8 +1,22 @@
-use crate::{evaluate::eval_7_2p2, prelude::*};
-use super::{EquityParams, EquityResults, preprocess_ranges, ProgressReporter};
+use crate::{evaluate::{eval_7_2p2_batch, BoardState}, prelude::*};
+use super::{histogram::ComboStrengths, runouts::CardResults, is_cancelled, CancelToken, EquityParams, EquityResults, preprocess_ranges, ProgressReporter, Snapshots};
 use rayon::prelude::*;
 
+// If cancelled, the results only cover the runouts enumerated so far.
 pub fn equity_enumerate(equity_params: EquityParams) -> Result<EquityResults> {
+    Ok(enumerate(equity_params, EnumerateOptions::default())?.results)
+}
+
+// What is kept from the runout loops besides the equity results.
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct EnumerateOptions {
+    // Number of bins for per combo hand strength histograms.
+    pub(super) bins:  Option<usize>,
+    // Results split by each card of the runout.
+    pub(super) cards: bool,
+}
+
+pub(super) fn enumerate(equity_params: EquityParams, options: EnumerateOptions) -> Result<EnumerateTask> {
 
     let board_cards = equity_params.board.as_vec();
     let (ranges, deck) = preprocess_ranges(equity_params.ranges, &board_cards)?;
@@ -13,10 +27,12 @@ pub fn equity_enumerate(equity_params: EquityParams) -> Result<EquityResults> {
         board: board_cards,
         lookup: equity_params.lookup,
         reporter: equity_params.reporter,
+        cancel: equity_params.cancel,
+        options,
     };
 
-    let results = if equity_params.board.is_river_dealt() {
-        enumerate_river(params)
+    let tasks = if equity_params.board.is_river_dealt() {
+        vec![enumerate_river(params)]
     } else if equity_params.board.is_turn_dealt() {
         enumerate_turn(params)
     } else if equity_params.board.is_flop_dealt() {
@@ -25,7 +41,7 @@ pub fn equity_enumerate(equity_params: EquityParams) -> Result<EquityResults> {
         enumerate_preflop(params)
     };
 
-    Ok(results)
+    Ok(EnumerateTask::combine(tasks))
 }
 
 struct EnumerateParams<'a> {
@@ -34,118 +50,166 @@ struct EnumerateParams<'a> {
     board:    Vec<Card>,
     lookup:   &'a [i32],
     reporter: Option<&'a dyn ProgressReporter>,
+    cancel:   Option<&'a CancelToken>,
+    options:  EnumerateOptions,
+}
+
+// Buffers and results of one parallel task of the runout loops.
+pub(super) struct EnumerateTask {
+    ranks:              Vec<Vec<u16>>,
+    matchup:            Vec<usize>,
+    pub(super) results: EquityResults,
+    pub(super) combos:  Option<ComboStrengths>,
+    pub(super) cards:   Option<CardResults>,
 }
 
-fn enumerate_preflop(params: EnumerateParams) -> EquityResults {
+impl EnumerateTask {
+    fn new(ranges: &[Vec<Hand>], board: &[Card], options: EnumerateOptions) -> Self {
+        let dealt = board.iter().fold(0, |mask, card| mask | card.mask());
+        Self {
+            ranks:   ranges.iter().map(|range| vec![0; range.len()]).collect(),
+            matchup: Vec::with_capacity(ranges.len()),
+            results: EquityResults::new(ranges.len()),
+            combos:  options.bins.map(|bins| ComboStrengths::new(ranges, bins)),
+            cards:   options.cards.then(|| CardResults::new(dealt, ranges.len())),
+        }
+    }
+
+    fn combine(tasks: Vec<EnumerateTask>) -> Self {
+        let mut tasks = tasks.into_iter();
+        let mut total = tasks.next().unwrap();
+        for task in tasks {
+            total.results = EquityResults::combine(vec![total.results, task.results]);
+            if let (Some(a), Some(b)) = (&mut total.combos, &task.combos) {
+                a.merge(b);
+            }
+            if let (Some(a), Some(b)) = (&mut total.cards, &task.cards) {
+                a.merge(b);
+            }
+        }
+        total
+    }
+}
+
+fn enumerate_preflop(params: EnumerateParams) -> Vec<EnumerateTask> {
     
     let deck = params.deck;
     let ranges = params.ranges;
+    let lookup = params.lookup;
+    let snapshots = Snapshots::new(params.reporter, deck.len(), ranges.len());
 
-    let results = (0..deck.len()).into_par_iter().map(|a| {
-        let mut cards = [Card::default(); 7];
-        cards[2] = deck[a];
-        let mut local_results = EquityResults::new(ranges.len());
+    // The board is walked through the lookup table first, each loop extending the state of the
+    // loop above it, so only the hole cards are left to evaluate per runout.
+    (0..deck.len()).into_par_iter().map(|a| {
+        let state_a = BoardState::default().add_card(deck[a], lookup);
+        let mut task = EnumerateTask::new(&ranges, &params.board, params.options);
 
         for b in (a + 1)..deck.len() {
-            cards[3] = deck[b];
+            let state_b = state_a.add_card(deck[b], lookup);
             for c in (b + 1)..deck.len() {
-                cards[4] = deck[c];
+                let state_c = state_b.add_card(deck[c], lookup);
                 for d in (c + 1)..deck.len() {
-                    cards[5] = deck[d];
+                    let state_d = state_c.add_card(deck[d], lookup);
                     for e in (d + 1)..deck.len() {
-                        cards[6] = deck[e];
+                        if is_cancelled(params.cancel) {
+                            return task;
+                        }
+                        let state = state_d.add_card(deck[e], lookup);
                         
-                        enumerate_board(&ranges, &mut local_results, &mut cards, &params.lookup);
+                        enumerate_board(&ranges, &mut task, &state, lookup);
                         if let Some(reporter) = params.reporter {
                             reporter.board_complete();
                         }
                     }
+                    if let Some(snapshots) = &snapshots {
+                        snapshots.update(a, &task.results);
+                    }
                 }
             }
         }
-        local_results
-    }).collect::<Vec<EquityResults>>();
-    
-    EquityResults::combine(results)
+        task
+    }).collect()
 }
 
-fn enumerate_flop(params: EnumerateParams) -> EquityResults {
+fn enumerate_flop(params: EnumerateParams) -> Vec<EnumerateTask> {
     
     let deck = params.deck;
     let ranges = params.ranges;
+    let lookup = params.lookup;
+    let snapshots = Snapshots::new(params.reporter, deck.len(), ranges.len());
+    let flop = BoardState::new(&params.board, lookup);
 
-    let results = (0..deck.len()).into_par_iter().map(|a| {
-        let mut cards = [Card::default(); 7];
-        cards[5] = deck[a];
-        cards[2..5].copy_from_slice(&params.board);
-        let mut local_results = EquityResults::new(ranges.len());
+    (0..deck.len()).into_par_iter().map(|a| {
+        let turn = flop.add_card(deck[a], lookup);
+        let mut task = EnumerateTask::new(&ranges, &params.board, params.options);
 
         for b in (a + 1)..deck.len() {
-            cards[6] = deck[b];
-            enumerate_board(&ranges, &mut local_results, &mut cards, &params.lookup);
+            if is_cancelled(params.cancel) {
+                break;
+            }
+            let river = turn.add_card(deck[b], lookup);
+            enumerate_board(&ranges, &mut task, &river, lookup);
             
             if let Some(reporter) = params.reporter {
                 reporter.board_complete();
             }
+            if let Some(snapshots) = &snapshots {
+                snapshots.update(a, &task.results);
+            }
         }
         
-        local_results
-    }).collect::<Vec<EquityResults>>();
-
-    EquityResults::combine(results)
+        task
+    }).collect()
 }
 
-fn enumerate_turn(params: EnumerateParams) -> EquityResults {
+fn enumerate_turn(params: EnumerateParams) -> Vec<EnumerateTask> {
     
     let deck = params.deck;
     let ranges = params.ranges;
-
-    let results = (0..deck.len()).into_par_iter().map(|a| {
-        let mut cards = [Card::default(); 7];
-        cards[6] = deck[a];
-        cards[2..6].copy_from_slice(&params.board);
-        let mut local_results = EquityResults::new(ranges.len());
+    let lookup = params.lookup;
+    let snapshots = Snapshots::new(params.reporter, deck.len(), ranges.len());
+    let turn = BoardState::new(&params.board, lookup);
+
+    (0..deck.len()).into_par_iter().map(|a| {
+        let river = turn.add_card(deck[a], lookup);
+        let mut task = EnumerateTask::new(&ranges, &params.board, params.options);
+        if is_cancelled(params.cancel) {
+            return task;
+        }
         
-        enumerate_board(&ranges, &mut local_results, &mut cards, &params.lookup);
+        enumerate_board(&ranges, &mut task, &river, lookup);
         if let Some(reporter) = params.reporter {
             reporter.board_complete();
         }
+        if let Some(snapshots) = &snapshots {
+            snapshots.update(a, &task.results);
+        }
 
-        local_results
-    }).collect::<Vec<EquityResults>>();
-
-    EquityResults::combine(results)
+        task
+    }).collect()
 }
 
-fn enumerate_river(params: EnumerateParams) -> EquityResults {
+fn enumerate_river(params: EnumerateParams) -> EnumerateTask {
     
     let ranges = params.ranges;
-    let board = params.board;
-
-    let mut results = EquityResults::new(ranges.len());
-    let mut cards = [Card::default(); 7];
-    cards[2] = board[0];
-    cards[3] = board[1];
-    cards[4] = board[2];
-    cards[5] = board[3];
-    cards[6] = board[4];
+    let river = BoardState::new(&params.board, params.lookup);
+
+    let mut task = EnumerateTask::new(&ranges, &params.board, params.options);
     
-    enumerate_board(&ranges, &mut results, &mut cards, &params.lookup);
+    enumerate_board(&ranges, &mut task, &river, params.lookup);
     if let Some(reporter) = params.reporter {
         reporter.board_complete();
     }
 
-    results
+    task
 }
 
+// Recurses over one combo per player, comparing the ranks evaluated for this board.
 fn enumerate_hands(
     ranges: &Vec<Vec<Hand>>,
     range_idx: usize,
     used_cards: &mut u64,
-    hands: &mut Vec<Hand>,
-    board: &mut [Card; 7],
-    lookup_table: &[i32],
-    results: &mut EquityResults,
+    task: &mut EnumerateTask,
 ) {
 
     // Base case, one hand assigned to each player.
@@ -154,12 +218,8 @@ fn enumerate_hands(
         let mut best_idxs = [0; 8];
         let mut best_idxs_count = 0;
         let mut best_rank = 0;
-        for (i, &hand) in hands.iter().enumerate() {
-
-            board[0] = hand.0;
-            board[1] = hand.1;
-            
-            let rank = eval_7_2p2(board, lookup_table);
+        for (i, &combo) in task.matchup.iter().enumerate() {
+            let rank = task.ranks[i][combo];
             if rank > best_rank {
                 best_idxs[0] = i;
                 best_idxs_count = 1;
@@ -170,20 +230,27 @@ fn enumerate_hands(
             }
         }
 
+        let results = &mut task.results;
+        let tie_value = 1.0 / best_idxs_count as f64;
         if best_idxs_count == 1 {
             results.wins[best_idxs[0]] += 1.0;
         } else {
-            let tie_value = 1.0 / best_idxs_count as f64;
             for idx in 0..best_idxs_count {
                 results.ties[best_idxs[idx]] += tie_value;
             }
         }
-
         results.total += 1.0;
+
+        if let Some(combos) = &mut task.combos {
+            for (player, &combo) in task.matchup.iter().enumerate() {
+                let share = if best_idxs[..best_idxs_count].contains(&player) { tie_value } else { 0.0 };
+                combos.add_matchup(player, combo, share);
+            }
+        }
         return;
     }
 
-    for hand in &ranges[range_idx] {
+    for (combo, hand) in ranges[range_idx].iter().enumerate() {
 
         let hand_mask = 1 << hand.0.0 | 1 << hand.1.0;
         if *used_cards & hand_mask != 0 {
@@ -192,25 +259,37 @@ fn enumerate_hands(
 
         *used_cards |= hand_mask;
 
-        hands.push(*hand);
-        enumerate_hands(ranges, range_idx + 1, used_cards, hands, board, lookup_table, results);
-        hands.pop();
+        task.matchup.push(combo);
+        enumerate_hands(ranges, range_idx + 1, used_cards, task);
+        task.matchup.pop();
 
         *used_cards &= !hand_mask;
     }
 }
 
+// Every combo shares the board, so each range is evaluated once per board from the board
+// state before enumerating matchups.  Combos holding a board card get a meaningless rank,
+// they are skipped as the board cards are already in use.
 fn enumerate_board(
     ranges: &Vec<Vec<Hand>>,
-    results: &mut EquityResults,
-    board: &mut [Card; 7],
+    task: &mut EnumerateTask,
+    board: &BoardState,
     lookup_table: &[i32],
 ) {
-    let mut hands = Vec::with_capacity(ranges.len());
-    let mut used_cards = 0_u64;
-    for card in board[2..].iter() {
-        used_cards |= 1 << card.0;
+    for (range, ranks) in ranges.iter().zip(task.ranks.iter_mut()) {
+        eval_7_2p2_batch(board, range, lookup_table, ranks);
     }
 
-    enumerate_hands(ranges, 0, &mut used_cards, &mut hands, board, lookup_table, results);
+    if let Some(cards) = &mut task.cards {
+        cards.board_start(&task.results);
+    }
+
+    let mut used_cards = board.mask();
+    enumerate_hands(ranges, 0, &mut used_cards, task);
+    if let Some(combos) = &mut task.combos {
+        combos.board_complete();
+    }
+    if let Some(cards) = &mut task.cards {
+        cards.board_complete(board.mask(), &task.results);
+    }
 }
@@ -0,0 +1,96 @@
+use std::io::Write;
+use rayon::prelude::*;
+use crate::{board::{canonical_flops, BoardTexture, SuitIsomorphism}, error::Error, prelude::*};
+use super::{equity_enumerate, EquityParams, EquityResults, ProgressReporter};
+
+// Equity of the ranges on a canonical flop, which stands in for weight isomorphic flops.
+#[derive(Debug, Clone)]
+pub struct FlopEquity {
+    pub board:   Board,
+    pub weight:  u32,
+    pub results: EquityResults,
+    pub texture: BoardTexture,
+}
+
+// Enumerates every runout of each of the 1,755 canonical flops, in parallel over the flops.
+// The reporter is told of each flop as it completes rather than of each runout.  Flops where
+// some range has no combos left have no results, giving NaN equities.
+pub fn flop_equities(ranges: &[Range], lookup: &[i32], reporter: Option<&dyn ProgressReporter>) -> Result<Vec<FlopEquity>> {
+    // Isomorphic flops only have the same equities if relabelling suits leaves the ranges alone.
+    for (i, range) in ranges.iter().enumerate() {
+        if !SuitIsomorphism::all().all(|isomorphism| isomorphism.range(range)[..] == range[..]) {
+            return Err(Error::AsymmetricRange(i + 1));
+        }
+    }
+
+    canonical_flops().into_par_iter().map(|flop| {
+        let params = EquityParams {
+            ranges:   ranges.to_vec(),
+            board:    flop.board.clone(),
+            lookup,
+            reporter: None,
+            seed:     None,
+            cancel:   None,
+        };
+        let results = equity_enumerate(params)?;
+        if let Some(reporter) = reporter {
+            reporter.board_complete();
+        }
+
+        Ok(FlopEquity {
+            texture: flop.board.texture().expect("canonical flops have three cards"),
+            board:   flop.board,
+            weight:  flop.weight,
+            results,
+        })
+    }).collect()
+}
+
+// Mean equity of each range over all flops, weighting each canonical flop by its class.  Not the
+// preflop equity, as every flop counts the same whichever hole cards block it.
+pub fn weighted_flop_equities(flops: &[FlopEquity]) -> Vec<f64> {
+    let num_players = flops.first().map_or(0, |flop| flop.results.wins.len());
+    let mut equities = vec![0.0; num_players];
+    let mut weight = 0.0;
+    for flop in flops.iter().filter(|flop| flop.results.total > 0.0) {
+        equities.iter_mut().zip(flop.results.equities()).for_each(|(a, b)| *a += b * flop.weight as f64);
+        weight += flop.weight as f64;
+    }
+    equities.iter_mut().for_each(|equity| *equity /= weight);
+    equities
+}
+
+// One row per flop with each range's equity and the flop's texture.
+pub fn write_flop_csv<W: Write>(flops: &[FlopEquity], range_str: &[String], writer: &mut W) -> std::io::Result<()> {
+    let ranges = range_str.iter()
+        .map(|range| format!("\"{}\"", range.replace('"', "\"\"")))
+        .collect::<Vec<String>>()
+        .join(",");
+    writeln!(writer, "flop,weight,{},suitedness,pairing,high_card,connectedness,straight_possible,flush_possible,wetness", ranges)?;
+
+    for flop in flops {
+        let board = flop.board.as_vec().iter()
+            .map(|card| format!("{}{}", card.rank(), card.suit().to_char()))
+            .collect::<String>();
+        let equities = flop.results.equities().iter()
+            .map(|equity| format!("{:.4}", equity))
+            .collect::<Vec<String>>()
+            .join(",");
+        let texture = &flop.texture;
+        writeln!(
+            writer,
+            "{},{},{},{:?},{:?},{},{},{},{},{:.3}",
+            board,
+            flop.weight,
+            equities,
+            texture.suitedness,
+            texture.pairing,
+            texture.high_card,
+            texture.connectedness,
+            texture.straight_possible,
+            texture.flush_possible,
+            texture.wetness,
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use crate::prelude::*;
+use super::{enumerate::{enumerate, EnumerateOptions}, EquityParams};
+
+// Hand strength of a combo over the runouts, its share of the pot against the other ranges
+// on each full board, for every runout where the combo can be dealt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HandStrength {
+    pub hand:      Hand,
+    pub runouts:   f64,
+    // E[HS], the mean strength over the runouts.
+    pub ehs:       f64,
+    // E[HS²], above E[HS]² the more the strength depends on the runout.
+    pub ehs2:      f64,
+    // Fraction of runouts with a strength in each equal width bin over [0, 1].
+    pub histogram: Vec<f64>,
+}
+
+impl HandStrength {
+    fn new(hand: Hand, bins: usize) -> Self {
+        Self { hand, runouts: 0.0, ehs: 0.0, ehs2: 0.0, histogram: vec![0.0; bins] }
+    }
+
+    // Accumulates sums, turned into means by finish.
+    fn add(&mut self, strength: f64) {
+        let bins = self.histogram.len();
+        self.runouts += 1.0;
+        self.ehs += strength;
+        self.ehs2 += strength * strength;
+        self.histogram[((strength * bins as f64) as usize).min(bins - 1)] += 1.0;
+    }
+
+    fn merge(&mut self, other: &HandStrength) {
+        self.runouts += other.runouts;
+        self.ehs += other.ehs;
+        self.ehs2 += other.ehs2;
+        self.histogram.iter_mut().zip(other.histogram.iter()).for_each(|(a, b)| *a += b);
+    }
+
+    fn finish(&mut self) {
+        if self.runouts > 0.0 {
+            self.ehs /= self.runouts;
+            self.ehs2 /= self.runouts;
+            self.histogram.iter_mut().for_each(|h| *h /= self.runouts);
+        }
+    }
+}
+
+// Per combo hand strength histograms for each range, enumerating every runout of the board.
+// Combos are in the order of Range::hand_combos, without those blocked by the board.
+pub fn equity_histograms(params: EquityParams, bins: usize) -> Result<Vec<Vec<HandStrength>>> {
+    assert!(bins > 0);
+
+    let enumeration = enumerate(params, EnumerateOptions { bins: Some(bins), ..Default::default() })?;
+    Ok(enumeration.combos.unwrap().finish())
+}
+
+// Strength of each combo on the current board, added to the totals once the board is done.
+#[derive(Debug, Clone)]
+pub(super) struct ComboStrengths {
+    board_share:    Vec<Vec<f64>>,
+    board_matchups: Vec<Vec<u32>>,
+    strengths:      Vec<Vec<HandStrength>>,
+}
+
+impl ComboStrengths {
+    pub(super) fn new(ranges: &[Vec<Hand>], bins: usize) -> Self {
+        Self {
+            board_share:    ranges.iter().map(|range| vec![0.0; range.len()]).collect(),
+            board_matchups: ranges.iter().map(|range| vec![0; range.len()]).collect(),
+            strengths:      ranges.iter().map(|range| {
+                range.iter().map(|&hand| HandStrength::new(hand, bins)).collect()
+            }).collect(),
+        }
+    }
+
+    #[inline]
+    pub(super) fn add_matchup(&mut self, player: usize, combo: usize, share: f64) {
+        self.board_share[player][combo] += share;
+        self.board_matchups[player][combo] += 1;
+    }
+
+    pub(super) fn board_complete(&mut self) {
+        for (player, strengths) in self.strengths.iter_mut().enumerate() {
+            let shares = self.board_share[player].iter_mut().zip(self.board_matchups[player].iter_mut());
+            for (strength, (share, matchups)) in strengths.iter_mut().zip(shares) {
+                if *matchups > 0 {
+                    strength.add(*share / *matchups as f64);
+                }
+                *share = 0.0;
+                *matchups = 0;
+            }
+        }
+    }
+
+    pub(super) fn merge(&mut self, other: &ComboStrengths) {
+        for (a, b) in self.strengths.iter_mut().flatten().zip(other.strengths.iter().flatten()) {
+            a.merge(b);
+        }
+    }
+
+    fn finish(mut self) -> Vec<Vec<HandStrength>> {
+        self.strengths.iter_mut().flatten().for_each(HandStrength::finish);
+        self.strengths
+    }
+}
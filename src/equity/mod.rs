@@ -1,16 +1,146 @@
+use std::{sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}, time::{Duration, Instant}};
 use prettytable::{Table, Row, Cell};
+use serde::{Deserialize, Serialize};
 use crate::prelude::*;
 
+mod distribution;
 mod enumerate;
+mod flops;
+mod histogram;
 mod monte_carlo;
+mod preflop;
+mod river;
+mod runouts;
+mod stratified;
 #[cfg(test)]
 mod tests;
 
+pub use distribution::{equity_distribution, EquityDistribution};
 pub use enumerate::equity_enumerate;
+pub use flops::{flop_equities, weighted_flop_equities, write_flop_csv, FlopEquity};
+pub use histogram::{equity_histograms, HandStrength};
 pub use monte_carlo::equity_monte_carlo;
+pub use preflop::{class_grid, hand_class, hand_class_combos, hand_class_name, preflop_class_equity, range_from_classes, PreflopEquities, NUM_CLASSES};
+pub use river::{river_analysis, RiverAction, RiverAnalysis, RiverCombo};
+pub use runouts::{runout_equities, RunoutEquities};
+pub use stratified::equity_stratified;
 
 pub trait ProgressReporter: Send + Sync {
     fn board_complete(&self);
+
+    // Called periodically with the results so far merged across threads.
+    fn snapshot(&self, _snapshot: &EquitySnapshot) {}
+
+    fn snapshot_interval(&self) -> Duration {
+        Duration::from_millis(250)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EquitySnapshot {
+    pub results: EquityResults,
+    pub elapsed: Duration,
+}
+
+impl EquitySnapshot {
+    // Matchups evaluated per second.
+    pub fn throughput(&self) -> f64 {
+        self.results.total / self.elapsed.as_secs_f64()
+    }
+}
+
+// Collects the running results of each parallel task and hands the merged
+// results to the reporter once its snapshot interval has passed.
+pub(crate) struct Snapshots<'a> {
+    reporter: &'a dyn ProgressReporter,
+    start:    Instant,
+    state:    Mutex<SnapshotState>,
+}
+
+struct SnapshotState {
+    tasks: Vec<EquityResults>,
+    last:  Instant,
+}
+
+impl<'a> Snapshots<'a> {
+
+    pub(crate) fn new(reporter: Option<&'a dyn ProgressReporter>, num_tasks: usize, num_players: usize) -> Option<Self> {
+        let now = Instant::now();
+        reporter.map(|reporter| Self {
+            reporter,
+            start: now,
+            state: Mutex::new(SnapshotState {
+                tasks: vec![EquityResults::new(num_players); num_tasks],
+                last:  now,
+            }),
+        })
+    }
+
+    pub(crate) fn update(&self, task: usize, results: &EquityResults) {
+        let mut state = self.state.lock().unwrap();
+        state.tasks[task].clone_from(results);
+
+        let now = Instant::now();
+        if now.duration_since(state.last) >= self.reporter.snapshot_interval() {
+            state.last = now;
+            let snapshot = EquitySnapshot {
+                results: EquityResults::combine(state.tasks.clone()),
+                elapsed: now.duration_since(self.start),
+            };
+            self.reporter.snapshot(&snapshot);
+        }
+    }
+}
+
+// Cooperative cancellation for a running calculation, either set explicitly
+// (e.g. from a signal handler) or after a deadline has passed.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+    deadline:  Option<Instant>,
+}
+
+impl CancelToken {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_deadline(deadline: Instant) -> Self {
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            deadline:  Some(deadline),
+        }
+    }
+
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self::with_deadline(Instant::now() + timeout)
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    // Underlying flag, set to true to cancel.  Can be passed to signal_hook::flag::register.
+    pub fn flag(&self) -> Arc<AtomicBool> {
+        self.cancelled.clone()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed) || self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+
+    // Token sharing this token's flag, also cancelled once the given deadline has passed.
+    pub fn until(&self, deadline: Instant) -> Self {
+        Self {
+            cancelled: self.cancelled.clone(),
+            deadline:  Some(self.deadline.map_or(deadline, |d| d.min(deadline))),
+        }
+    }
+}
+
+pub(crate) fn is_cancelled(cancel: Option<&CancelToken>) -> bool {
+    cancel.is_some_and(|c| c.is_cancelled())
 }
 
 pub struct EquityParams<'a> {
@@ -18,14 +148,59 @@ pub struct EquityParams<'a> {
     pub board:    Board,
     pub lookup:   &'a [i32],
     pub reporter: Option<&'a dyn ProgressReporter>,
+    // Seed for Monte Carlo sampling, the same seed and thread count give identical results.
+    pub seed:     Option<u64>,
+    // Stops the calculation early, the results then only cover the work done so far.
+    pub cancel:   Option<&'a CancelToken>,
 }
 
+#[derive(Debug, Clone, Copy)]
 pub enum EquityMethod {
     Enumerate,
-    MonteCarlo(Option<usize>),
+    MonteCarlo(Option<u64>),
+    // Quasi-Monte Carlo, spreads samples evenly over combos and runouts.
+    Stratified(Option<u64>),
+    // Enumerates if it is estimated to finish within the time budget, otherwise samples until it runs out.
+    Auto(Duration),
 }
 
-#[derive(Debug, Clone)]
+// Rough single thread throughput of the enumeration, in hand evaluations per second.
+const EVALUATIONS_PER_SECOND: f64 = 50_000_000.0;
+
+pub fn calculate_equity(params: EquityParams, method: EquityMethod) -> Result<EquityResults> {
+    match method {
+        EquityMethod::Enumerate => equity_enumerate(params),
+        EquityMethod::MonteCarlo(iterations) => equity_monte_carlo(params, iterations),
+        EquityMethod::Stratified(iterations) => equity_stratified(params, iterations),
+        EquityMethod::Auto(budget) => {
+            let start = Instant::now();
+            if estimate_enumeration_time(&params.ranges, &params.board) <= budget {
+                return equity_enumerate(params);
+            }
+
+            let cancel = params.cancel.map_or_else(
+                || CancelToken::with_deadline(start + budget),
+                |cancel| cancel.until(start + budget),
+            );
+            equity_stratified(EquityParams { cancel: Some(&cancel), ..params }, None)
+        },
+    }
+}
+
+// Estimated time to enumerate every runout and matchup, ignoring card removal between players.
+// Estimates past what a Duration holds are Duration::MAX.
+pub fn estimate_enumeration_time(ranges: &[Range], board: &Board) -> Duration {
+    let dead = board.mask();
+    let matchups = ranges.iter()
+        .map(|range| range.hand_combos(dead).len() as f64)
+        .product::<f64>();
+    let evaluations = board.num_runouts() as f64 * matchups * ranges.len() as f64;
+    let threads = rayon::current_num_threads() as f64;
+
+    Duration::try_from_secs_f64(evaluations / (EVALUATIONS_PER_SECOND * threads)).unwrap_or(Duration::MAX)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EquityResults {
     pub wins:   Vec<f64>,
     pub ties:   Vec<f64>,
@@ -52,10 +227,11 @@ impl EquityResults {
         total
     }
 
+    // Ties are already counted as each player's share of the pot.
     pub fn equities(&self) -> Vec<f64> {
         let mut equities = vec![0.0; self.wins.len()];
         for i in 0..self.wins.len() {
-            equities[i] = ((self.wins[i] + self.ties[i] / 2.0) / self.total) * 100.0;
+            equities[i] = ((self.wins[i] + self.ties[i]) / self.total) * 100.0;
         }
         equities
     }
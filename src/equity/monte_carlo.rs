@@ -1,17 +1,18 @@
-use std::sync::{atomic::{AtomicBool, Ordering}, Arc};
-use crate::{evaluate::eval_7_2p2, prelude::*};
-use super::{preprocess_ranges, EquityParams, EquityResults, ProgressReporter};
+use crate::{evaluate::eval_7_2p2, error::Error, prelude::*};
+use super::{is_cancelled, preprocess_ranges, CancelToken, EquityParams, EquityResults, ProgressReporter, Snapshots};
 use rayon::prelude::*;
-use signal_hook::flag;
+use fastrand::Rng;
 
 pub fn equity_monte_carlo(equity_params: EquityParams, iterations: Option<u64>) -> Result<EquityResults> {
 
     let board = equity_params.board.as_vec();
     let (ranges, deck) = preprocess_ranges(equity_params.ranges, &board)?;
-
-    let running = Arc::new(AtomicBool::new(true));
-    let r = running.clone();
-    flag::register(signal_hook::consts::SIGINT, r)?;
+    if iterations.is_none() && equity_params.cancel.is_none() {
+        return Err(Error::NoStoppingCondition);
+    }
+    if !has_valid_matchup(&ranges, 0) {
+        return Err(Error::NoValidCombos);
+    }
 
     let params = MonteCarloParams {
         ranges,
@@ -20,22 +21,18 @@ pub fn equity_monte_carlo(equity_params: EquityParams, iterations: Option<u64>)
         lookup: equity_params.lookup,
         iterations,
         reporter: equity_params.reporter,
-        running,
+        seed: equity_params.seed,
+        cancel: equity_params.cancel,
     };
 
-    let results = if equity_params.board.is_river_dealt() {
-        monte_carlo_river(params)
-    } else if equity_params.board.is_turn_dealt() {
-        monte_carlo_turn(params)
-    } else if equity_params.board.is_flop_dealt() {
-        monte_carlo_flop(params)
-    } else {
-        monte_carlo_preflop(params)
-    };
+    let results = monte_carlo_runouts(params);
 
     Ok(results)
 }
 
+// Iterations between each thread publishing its results for snapshots.
+pub(super) const SNAPSHOT_ITERATIONS: u64 = 1024;
+
 struct MonteCarloParams<'a> {
     ranges:     Vec<Vec<Hand>>,
     deck:       Deck,
@@ -43,74 +40,45 @@ struct MonteCarloParams<'a> {
     lookup:     &'a [i32],
     iterations: Option<u64>,
     reporter:   Option<&'a dyn ProgressReporter>,
-    running:    Arc<AtomicBool>,
+    seed:       Option<u64>,
+    cancel:     Option<&'a CancelToken>,
 }
 
-fn monte_carlo_preflop(params: MonteCarloParams) -> EquityResults {
+// Each iteration samples a matchup, then deals the rest of the board from the cards left.
+// Every thread reuses its own deck and hand buffers, so the loop doesn't allocate.
+fn monte_carlo_runouts(params: MonteCarloParams) -> EquityResults {
 
     let num_threads = rayon::current_num_threads() as u64;
-    let iterations_per_thread = params.iterations.map(|i| (i + num_threads - 1) / num_threads);
+    let iterations_per_thread = params.iterations.map(|i| i.div_ceil(num_threads));
+    let snapshots = Snapshots::new(params.reporter, num_threads as usize, params.ranges.len());
+    let runout_len = 5 - params.board.len();
     
-    let results = (0..num_threads).into_par_iter().map(|_| {
-
-        let mut local_results = EquityResults::new(params.ranges.len());
-        let mut cards = [Card::default(); 7];
-        let mut iteration = 0;
-        
-        while params.running.load(Ordering::Relaxed) && (iterations_per_thread.is_none() || iteration < iterations_per_thread.unwrap()) {
-            
-            let mut available_deck = params.deck.clone();
-            available_deck.shuffle();
-            let first_five = &available_deck.as_ref()[0..5];
-            cards[2..7].copy_from_slice(first_five);
-            
-            let mut used_cards = 0u64;
-            for card in &cards[2..7] {
-                used_cards |= 1 << card.0;
-            }
-            
-            monte_carlo_sample_hands(&params.ranges, &mut local_results, &mut cards, params.lookup, used_cards);            
-            
-            iteration += 1;
-            if let Some(reporter) = params.reporter {
-                reporter.board_complete();
-            }
-        }
-        local_results
-    }).collect::<Vec<EquityResults>>();
-
-    EquityResults::combine(results)
-}
+    let results = thread_rngs(params.seed, num_threads).into_par_iter().enumerate().map(|(thread, mut rng)| {
 
-fn monte_carlo_flop(params: MonteCarloParams) -> EquityResults {
-    let num_threads = rayon::current_num_threads() as u64;
-    let iterations_per_thread = params.iterations.map(|i| (i + num_threads - 1) / num_threads);
-    
-    let results = (0..num_threads).into_par_iter().map(|_| {
         let mut local_deck = params.deck.clone();
         let mut local_results = EquityResults::new(params.ranges.len());
+        let mut hands = vec![Hand(Card::default(), Card::default()); params.ranges.len()];
         let mut cards = [Card::default(); 7];
-        cards[2..5].copy_from_slice(&params.board);
+        cards[2..7 - runout_len].copy_from_slice(&params.board);
+        let board_mask = board_mask(&params.board);
         let mut iteration = 0;
         
-        while params.running.load(Ordering::Relaxed) && (iterations_per_thread.is_none() || iteration < iterations_per_thread.unwrap()) {
-            
-            local_deck.shuffle();
-            
-            cards[5] = local_deck[0];
-            cards[6] = local_deck[1];
+        while !is_cancelled(params.cancel) && (iterations_per_thread.is_none() || iteration < iterations_per_thread.unwrap()) {
             
-            let mut used_cards = 0u64;
-            for card in cards[2..].iter() {
-                used_cards |= 1 << card.0;
-            }
+            let used_cards = monte_carlo_sample_hands(&params.ranges, &mut hands, &mut rng, board_mask);
+            local_deck.deal_random(&mut rng, used_cards, &mut cards[7 - runout_len..]);
             
-            monte_carlo_sample_hands(&params.ranges, &mut local_results, &mut cards, params.lookup, used_cards);
+            showdown(&hands, &mut local_results, &mut cards, params.lookup);
             
             iteration += 1;
             if let Some(reporter) = params.reporter {
                 reporter.board_complete();
             }
+            if let Some(snapshots) = &snapshots {
+                if iteration % SNAPSHOT_ITERATIONS == 0 {
+                    snapshots.update(thread, &local_results);
+                }
+            }
         }
         
         local_results
@@ -119,130 +87,101 @@ fn monte_carlo_flop(params: MonteCarloParams) -> EquityResults {
     EquityResults::combine(results)
 }
 
-fn monte_carlo_turn(params: MonteCarloParams) -> EquityResults {
-    let num_threads = rayon::current_num_threads() as u64;
-    let iterations_per_thread = params.iterations.map(|i| (i + num_threads - 1) / num_threads);
-    
-    let results = (0..num_threads).into_par_iter().map(|_| {
-        
-        let mut local_deck = params.deck.clone();
-        let mut local_results = EquityResults::new(params.ranges.len());
-        let mut cards = [Card::default(); 7];
-        cards[2..6].copy_from_slice(&params.board);
-        let mut iteration = 0;
-        
-        while params.running.load(Ordering::Relaxed) && (iterations_per_thread.is_none() || iteration < iterations_per_thread.unwrap()) {
-            
-            local_deck.shuffle();
-            cards[6] = local_deck[0];
-            
-            let mut used_cards = 0u64;
-            for card in cards[2..].iter() {
-                used_cards |= 1 << card.0;
-            }
-            
-            monte_carlo_sample_hands(&params.ranges, &mut local_results, &mut cards, params.lookup, used_cards);            
-            iteration += 1;
-            if let Some(reporter) = params.reporter {
-                reporter.board_complete();
-            }
-        }
-        
-        local_results
-    }).collect::<Vec<EquityResults>>();
-    
-    EquityResults::combine(results)
+// One RNG per thread.  With a seed the per-thread streams are forked from a single seeded
+// generator, so a run is reproducible for a fixed seed and thread count.
+pub(super) fn thread_rngs(seed: Option<u64>, num_threads: u64) -> Vec<Rng> {
+    let mut root = seed.map_or_else(Rng::new, Rng::with_seed);
+    (0..num_threads).map(|_| root.fork()).collect()
 }
 
-fn monte_carlo_river(params: MonteCarloParams) -> EquityResults {
-    let num_threads = rayon::current_num_threads() as u64;
-    let iterations_per_thread = params.iterations.map(|i | (i + num_threads - 1) / num_threads);
+pub(super) fn board_mask(board: &[Card]) -> u64 {
+    board.iter().fold(0, |mask, card| mask | card.mask())
+}
 
-    let results = (0..num_threads).into_par_iter().map(|_| {
-        let mut local_results = EquityResults::new(params.ranges.len());
-        let mut cards = [Card::default(); 7];
-        cards[2..7].copy_from_slice(&params.board);
-        let mut iteration = 0;
-        
-        let mut used_cards = 0u64;
-        for card in &params.board {
-            used_cards |= 1 << card.0;
+// Samples one hand per player from the joint distribution of non-conflicting combos.
+// Drawing each player independently and rejecting the whole draw on any card collision
+// gives every valid matchup the same weight as in enumeration, regardless of player order.
+// Returns the mask of all cards in use, board included.
+fn monte_carlo_sample_hands(
+    ranges: &[Vec<Hand>],
+    hands: &mut [Hand],
+    rng: &mut Rng,
+    board_mask: u64,
+) -> u64 {
+    loop {
+        if let Some(used_cards) = try_sample_hands(ranges, hands, rng, board_mask) {
+            return used_cards;
         }
-        
-        while params.running.load(Ordering::Relaxed) && (iterations_per_thread.is_none() || iteration < iterations_per_thread.unwrap()) {
-            monte_carlo_sample_hands(&params.ranges, &mut local_results, &mut cards, params.lookup, used_cards);
-            iteration += 1;
-            if let Some(reporter) = params.reporter {
-                reporter.board_complete();
-            }
+    }
+}
+
+// A single draw of one hand per player, None if any two hands collide.
+pub(super) fn try_sample_hands(
+    ranges: &[Vec<Hand>],
+    hands: &mut [Hand],
+    rng: &mut Rng,
+    used_cards: u64,
+) -> Option<u64> {
+    let mut used_cards = used_cards;
+    
+    for (range, slot) in ranges.iter().zip(hands.iter_mut()) {
+        let hand = range[rng.usize(0..range.len())];
+        let hand_mask = hand.mask();
+        if used_cards & hand_mask != 0 {
+            return None;
         }
         
-        local_results
-    }).collect::<Vec<EquityResults>>();
+        used_cards |= hand_mask;
+        *slot = hand;
+    }
     
-    EquityResults::combine(results)
+    Some(used_cards)
 }
 
-fn monte_carlo_sample_hands(
-    ranges: &Vec<Vec<Hand>>,
+// Checks that at least one matchup exists where no two players share a card,
+// otherwise rejection sampling would never terminate.
+pub(super) fn has_valid_matchup(ranges: &[Vec<Hand>], used_cards: u64) -> bool {
+    match ranges.split_first() {
+        None => true,
+        Some((range, rest)) => range.iter().any(|hand| {
+            hand.mask() & used_cards == 0 && has_valid_matchup(rest, used_cards | hand.mask())
+        }),
+    }
+}
+
+pub(super) fn showdown(
+    hands: &[Hand],
     results: &mut EquityResults,
     board: &mut [Card; 7],
     lookup_table: &[i32],
-    used_cards: u64,
 ) {
+    let mut best_idxs = [0; 8];
+    let mut best_idxs_count = 0;
+    let mut best_rank = 0;
     
-    let mut hands = Vec::with_capacity(ranges.len());
-    let mut current_used_cards = used_cards;
-    
-    for range in ranges {
-        let mut valid_hands = Vec::new();
-        
-        for hand in range {
-            let hand_mask = 1 << hand.0.0 | 1 << hand.1.0;
-            if current_used_cards & hand_mask == 0 {
-                valid_hands.push(*hand);
-            }
-        }
+    for (i, &hand) in hands.iter().enumerate() {
+        board[0] = hand.0;
+        board[1] = hand.1;
         
-        if valid_hands.is_empty() {
-            return;
+        let rank = eval_7_2p2(board, lookup_table);
+        if rank > best_rank {
+            best_idxs[0] = i;
+            best_idxs_count = 1;
+            best_rank = rank;
+        } else if rank == best_rank {
+            best_idxs[best_idxs_count] = i;
+            best_idxs_count += 1;
         }
-        
-        let idx = fastrand::usize(0..valid_hands.len());
-        let selected_hand = valid_hands[idx];
-        hands.push(selected_hand);
-        current_used_cards |= 1 << selected_hand.0.0 | 1 << selected_hand.1.0;
     }
     
-    if hands.len() == ranges.len() {
-        let mut best_idxs = [0; 8];
-        let mut best_idxs_count = 0;
-        let mut best_rank = 0;
-        
-        for (i, &hand) in hands.iter().enumerate() {
-            board[0] = hand.0;
-            board[1] = hand.1;
-            
-            let rank = eval_7_2p2(board, lookup_table);
-            if rank > best_rank {
-                best_idxs[0] = i;
-                best_idxs_count = 1;
-                best_rank = rank;
-            } else if rank == best_rank {
-                best_idxs[best_idxs_count] = i;
-                best_idxs_count += 1;
-            }
-        }
-        
-        if best_idxs_count == 1 {
-            results.wins[best_idxs[0]] += 1.0;
-        } else {
-            let tie_value = 1.0 / best_idxs_count as f64;
-            for idx in 0..best_idxs_count {
-                results.ties[best_idxs[idx]] += tie_value;
-            }
+    if best_idxs_count == 1 {
+        results.wins[best_idxs[0]] += 1.0;
+    } else {
+        let tie_value = 1.0 / best_idxs_count as f64;
+        for &idx in &best_idxs[..best_idxs_count] {
+            results.ties[idx] += tie_value;
         }
-        
-        results.total += 1.0;
     }
-}
\ No newline at end of file
+    
+    results.total += 1.0;
+}
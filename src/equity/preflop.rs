@@ -0,0 +1,187 @@
+use std::{io::{self, Read, Write}, path::Path};
+use rayon::prelude::*;
+use crate::{card::Rank, error::Error, prelude::*, range::{offsuit_idxs, pair_idxs, suited_idxs}};
+use super::{equity_enumerate, EquityParams, ProgressReporter};
+
+pub const NUM_CLASSES: usize = 169;
+
+// Starting hand classes index the 13x13 grid row by row from the aces, with pairs on the diagonal,
+// suited hands above it and offsuit hands below.
+pub fn hand_class(hand: &Hand) -> usize {
+    let (high, low) = (hand.0.rank_u8().max(hand.1.rank_u8()), hand.0.rank_u8().min(hand.1.rank_u8()));
+    let (high, low) = (12 - high as usize, 12 - low as usize);
+    if hand.suited() {
+        high * 13 + low
+    } else {
+        low * 13 + high
+    }
+}
+
+pub fn hand_class_combos(class: usize) -> Vec<Hand> {
+    let (row, col) = (class / 13, class % 13);
+    let idxs = match row.cmp(&col) {
+        std::cmp::Ordering::Equal => pair_idxs(Rank::from(12 - row as u8)),
+        std::cmp::Ordering::Less => suited_idxs(Rank::from(12 - row as u8), Rank::from(12 - col as u8)),
+        std::cmp::Ordering::Greater => offsuit_idxs(Rank::from(12 - col as u8), Rank::from(12 - row as u8)),
+    };
+    idxs.into_iter().map(Hand::from_idx).collect()
+}
+
+// Eg. "AKs", "QJo" or "77".
+pub fn hand_class_name(class: usize) -> String {
+    let (row, col) = (class / 13, class % 13);
+    let rank = |i: usize| Rank::from(12 - i as u8);
+    match row.cmp(&col) {
+        std::cmp::Ordering::Equal => format!("{}{}", rank(row), rank(col)),
+        std::cmp::Ordering::Less => format!("{}{}s", rank(row), rank(col)),
+        std::cmp::Ordering::Greater => format!("{}{}o", rank(col), rank(row)),
+    }
+}
+
+// Range holding the classes played at least half the time.
+pub fn range_from_classes(frequencies: &[f64]) -> Range {
+    let mut range = Range::default();
+    for (class, &frequency) in frequencies.iter().enumerate() {
+        if frequency >= 0.5 {
+            hand_class_combos(class).iter().for_each(|hand| range.set_hand(hand, true));
+        }
+    }
+    range
+}
+
+// The 13x13 grid with the classes played at least half the time.
+pub fn class_grid(frequencies: &[f64]) -> String {
+    let mut grid = String::new();
+    for row in 0..13 {
+        for col in 0..13 {
+            let class = row * 13 + col;
+            if frequencies[class] >= 0.5 {
+                grid.push_str(&format!("{:<4}", hand_class_name(class)));
+            } else {
+                grid.push_str(" .  ");
+            }
+        }
+        grid.push('\n');
+    }
+    grid
+}
+
+// Heads-up all-in equity of each starting hand class against each other, averaged over the pairs
+// of combos that don't share a card.
+#[derive(Debug, Clone)]
+pub struct PreflopEquities {
+    // Equity of the row class against the column class.
+    equities: Vec<f64>,
+    // Pairs of combos of the two classes without a shared card.
+    pairs:    Vec<f64>,
+}
+
+impl PreflopEquities {
+    pub fn from_vec(equities: Vec<f64>) -> Self {
+        assert_eq!(equities.len(), NUM_CLASSES * NUM_CLASSES);
+
+        let combos = (0..NUM_CLASSES).map(hand_class_combos).collect::<Vec<Vec<Hand>>>();
+        let mut pairs = vec![0.0; NUM_CLASSES * NUM_CLASSES];
+        for (a, hero) in combos.iter().enumerate() {
+            for (b, villain) in combos.iter().enumerate() {
+                pairs[a * NUM_CLASSES + b] = hero.iter()
+                    .flat_map(|h| villain.iter().map(move |v| h.mask() & v.mask() == 0))
+                    .filter(|&compatible| compatible)
+                    .count() as f64;
+            }
+        }
+        Self { equities, pairs }
+    }
+
+    // Enumerates every board for each pair of classes, in parallel over the pairs.  Takes a while,
+    // save the table to load it next time.  The reporter is told of each pair as it completes.
+    pub fn compute(lookup: &[i32], reporter: Option<&dyn ProgressReporter>) -> Result<Self> {
+        let pairs = (0..NUM_CLASSES)
+            .flat_map(|a| ((a + 1)..NUM_CLASSES).map(move |b| (a, b)))
+            .collect::<Vec<(usize, usize)>>();
+
+        let results = pairs.par_iter().map(|&(a, b)| {
+            let equity = preflop_class_equity(a, b, lookup)?;
+            if let Some(reporter) = reporter {
+                reporter.board_complete();
+            }
+            Ok(equity)
+        }).collect::<Result<Vec<f64>>>()?;
+
+        let mut equities = vec![0.5; NUM_CLASSES * NUM_CLASSES];
+        for (&(a, b), equity) in pairs.iter().zip(results) {
+            equities[a * NUM_CLASSES + b] = equity;
+            equities[b * NUM_CLASSES + a] = 1.0 - equity;
+        }
+        Ok(Self::from_vec(equities))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut buffer = Vec::new();
+        std::fs::File::open(path)
+            .and_then(|mut file| file.read_to_end(&mut buffer))
+            .map_err(Error::PreflopTableError)?;
+        if buffer.len() != NUM_CLASSES * NUM_CLASSES * 8 {
+            return Err(Error::PreflopTableError(io::Error::new(io::ErrorKind::UnexpectedEof, "wrong table size")));
+        }
+
+        let equities = buffer.chunks_exact(8)
+            .map(|bytes| f64::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        Ok(Self::from_vec(equities))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let bytes = self.equities.iter().flat_map(|equity| equity.to_le_bytes()).collect::<Vec<u8>>();
+        std::fs::File::create(path)
+            .and_then(|mut file| file.write_all(&bytes))
+            .map_err(Error::PreflopTableError)
+    }
+
+    pub fn equity(&self, hero: usize, villain: usize) -> f64 {
+        self.equities[hero * NUM_CLASSES + villain]
+    }
+
+    pub fn combo_pairs(&self, hero: usize, villain: usize) -> f64 {
+        self.pairs[hero * NUM_CLASSES + villain]
+    }
+
+    // Equity of a hero class against a villain playing each class with the given frequency, and
+    // the combos of the villain's range the hero's hand leaves.  The equity is NaN if none are.
+    pub fn equity_vs_range(&self, hero: usize, frequencies: &[f64]) -> (f64, f64) {
+        let row = hero * NUM_CLASSES;
+        let hero_combos = match (hero / 13).cmp(&(hero % 13)) {
+            std::cmp::Ordering::Equal => 6.0,
+            std::cmp::Ordering::Less => 4.0,
+            std::cmp::Ordering::Greater => 12.0,
+        };
+        let mut weight = 0.0;
+        let mut equity = 0.0;
+        for (villain, &frequency) in frequencies.iter().enumerate() {
+            let pairs = self.pairs[row + villain] * frequency;
+            weight += pairs;
+            equity += pairs * self.equities[row + villain];
+        }
+        (equity / weight, weight / hero_combos)
+    }
+}
+
+// Equity of one class against another, by symmetry the same as of any one combo of the first
+// against the other's combos it leaves.
+pub fn preflop_class_equity(hero: usize, villain: usize, lookup: &[i32]) -> Result<f64> {
+    let mut hero_range = Range::default();
+    hero_range.set_hand(&hand_class_combos(hero)[0], true);
+    let mut villain_range = Range::default();
+    hand_class_combos(villain).iter().for_each(|hand| villain_range.set_hand(hand, true));
+
+    let params = EquityParams {
+        ranges:   vec![hero_range, villain_range],
+        board:    Board::default(),
+        lookup,
+        reporter: None,
+        seed:     None,
+        cancel:   None,
+    };
+    let results = equity_enumerate(params)?;
+    Ok((results.wins[0] + results.ties[0]) / results.total)
+}
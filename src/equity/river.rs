@@ -0,0 +1,130 @@
+use std::cmp::{Ordering, Reverse};
+use prettytable::{Table, Row, Cell};
+use crate::{evaluate::BoardState, error::Error, prelude::*};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiverAction {
+    Value,
+    Bluff,
+    Check,
+}
+
+#[derive(Debug, Clone)]
+pub struct RiverCombo {
+    pub hand:          Hand,
+    // Share of the pot against the whole calling range, and against the part that defends.
+    pub equity:        f64,
+    pub called_equity: f64,
+    pub action:        RiverAction,
+}
+
+// A polarized bet on a dealt river.  The caller defends the strongest MDF of their range, the
+// bettor value bets what beats half of those calls and bluffs their weakest hands in proportion.
+#[derive(Debug, Clone)]
+pub struct RiverAnalysis {
+    // Bet as a fraction of the pot.
+    pub bet:         f64,
+    // Minimum defence frequency, the share of the calling range that keeps bluffs from profiting.
+    pub mdf:         f64,
+    // How often a bluff needs a fold to break even.
+    pub alpha:       f64,
+    // Bluffs per value bet that leave the caller indifferent.
+    pub bluff_ratio: f64,
+    // Betting range, strongest first.
+    pub combos:      Vec<RiverCombo>,
+    // Calling combos that defend, strongest first.
+    pub defending:   Vec<Hand>,
+}
+
+impl RiverAnalysis {
+    pub fn value_combos(&self) -> usize {
+        self.combos.iter().filter(|combo| combo.action == RiverAction::Value).count()
+    }
+
+    pub fn bluff_combos(&self) -> usize {
+        self.combos.iter().filter(|combo| combo.action == RiverAction::Bluff).count()
+    }
+
+    pub fn print(&self) {
+        println!("Bet {:.0}% of the pot, MDF {:.1}%, alpha {:.1}%", self.bet * 100.0, self.mdf * 100.0, self.alpha * 100.0);
+        println!(
+            "{} value and {} bluff combos, {:.2} bluffs per value bet is balanced",
+            self.value_combos(), self.bluff_combos(), self.bluff_ratio,
+        );
+
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![Cell::new("Hand"), Cell::new("Equity"), Cell::new("Called"), Cell::new("Action")]));
+        for combo in &self.combos {
+            table.add_row(Row::new(vec![
+                Cell::new(&format!("{}{}", combo.hand.0, combo.hand.1)),
+                Cell::new(&format!("{:.1}%", combo.equity * 100.0)),
+                Cell::new(&format!("{:.1}%", combo.called_equity * 100.0)),
+                Cell::new(&format!("{:?}", combo.action)),
+            ]));
+        }
+        table.printstd();
+    }
+}
+
+// Splits the betting range into value bets, bluffs and checks for a bet of the given fraction of
+// the pot, ranking every combo on the river against the calling combos it doesn't block.
+pub fn river_analysis(betting: &Range, calling: &Range, board: &Board, bet: f64, lookup: &[i32]) -> Result<RiverAnalysis> {
+    if !(bet.is_finite() && bet > 0.0) {
+        return Err(Error::InvalidBetSize(bet));
+    }
+    let num_cards = board.as_vec().len();
+    if !board.is_river_dealt() {
+        return Err(Error::InvalidRiverBoard(num_cards));
+    }
+
+    let state = BoardState::new(&board.as_vec(), lookup);
+    let rank = |hands: Vec<Hand>| hands.into_iter().map(|hand| (hand, state.eval_hand(&hand, lookup))).collect::<Vec<_>>();
+    let betting = rank(betting.hand_combos(board.mask()));
+    let mut calling = rank(calling.hand_combos(board.mask()));
+    if betting.is_empty() || calling.is_empty() {
+        return Err(Error::NoValidCombos);
+    }
+
+    let mdf = 1.0 / (1.0 + bet);
+    let alpha = bet / (1.0 + bet);
+    calling.sort_by_key(|&(_, rank)| Reverse(rank));
+    let defending = &calling[..((calling.len() as f64 * mdf).ceil() as usize)];
+
+    let equity = |hand: &Hand, rank: u16, against: &[(Hand, u16)]| {
+        let (share, count) = against.iter()
+            .filter(|(other, _)| other.mask() & hand.mask() == 0)
+            .fold((0.0, 0.0), |(share, count), (_, other)| match rank.cmp(other) {
+                Ordering::Greater => (share + 1.0, count + 1.0),
+                Ordering::Equal => (share + 0.5, count + 1.0),
+                Ordering::Less => (share, count + 1.0),
+            });
+        if count > 0.0 { share / count } else { 0.0 }
+    };
+    let mut combos = betting.iter().map(|&(hand, rank)| RiverCombo {
+        hand,
+        equity:        equity(&hand, rank, &calling),
+        called_equity: equity(&hand, rank, defending),
+        action:        RiverAction::Check,
+    }).collect::<Vec<RiverCombo>>();
+    combos.sort_by(|a, b| b.called_equity.total_cmp(&a.called_equity).then(b.equity.total_cmp(&a.equity)));
+
+    let mut value = 0;
+    for combo in combos.iter_mut().filter(|combo| combo.called_equity > 0.5) {
+        combo.action = RiverAction::Value;
+        value += 1;
+    }
+    // The weakest hands bluff, they have the least to gain from checking.
+    let bluffs = (value as f64 * alpha).round() as usize;
+    for combo in combos.iter_mut().rev().filter(|combo| combo.action == RiverAction::Check).take(bluffs) {
+        combo.action = RiverAction::Bluff;
+    }
+
+    Ok(RiverAnalysis {
+        bet,
+        mdf,
+        alpha,
+        bluff_ratio: alpha,
+        combos,
+        defending: defending.iter().map(|&(hand, _)| hand).collect(),
+    })
+}
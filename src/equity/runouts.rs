@@ -0,0 +1,96 @@
+use prettytable::{Table, Row, Cell};
+use crate::{error::Error, prelude::*};
+use super::{enumerate::{enumerate, EnumerateOptions}, EquityParams, EquityResults};
+
+// Equity on the next card, for each card that can come on a flop or turn board.
+#[derive(Debug, Clone)]
+pub struct RunoutEquities {
+    pub total: EquityResults,
+    // On a flop the results for each turn card, taken over every river after it.
+    pub cards: Vec<(Card, EquityResults)>,
+}
+
+pub fn runout_equities(params: EquityParams) -> Result<RunoutEquities> {
+    let num_cards = params.board.as_vec().len();
+    if num_cards != 3 && num_cards != 4 {
+        return Err(Error::InvalidRunoutBoard(num_cards));
+    }
+
+    let enumeration = enumerate(params, EnumerateOptions { cards: true, ..Default::default() })?;
+    let cards = enumeration.cards.unwrap().results
+        .into_iter()
+        .enumerate()
+        .filter(|(_, results)| results.total > 0.0)
+        .map(|(card, results)| (Card(card as u8), results))
+        .collect();
+
+    Ok(RunoutEquities { total: enumeration.results, cards })
+}
+
+impl RunoutEquities {
+    // One row per card, with each range's equity and its change from the current board.
+    pub fn print(&self, range_str: &[String]) {
+        let mut table = Table::new();
+        let mut header = vec![Cell::new("Card")];
+        header.extend(range_str.iter().map(|range| Cell::new(range)));
+        table.add_row(Row::new(header));
+
+        let total = self.total.equities();
+        let mut row = vec![Cell::new("All")];
+        row.extend(total.iter().map(|equity| Cell::new(&format!("{:.2}%", equity))));
+        table.add_row(Row::new(row));
+
+        for (card, results) in &self.cards {
+            let mut row = vec![Cell::new(&card.to_string())];
+            row.extend(results.equities().iter().zip(total.iter()).map(|(equity, total)| {
+                Cell::new(&format!("{:.2}% ({:+.2})", equity, equity - total))
+            }));
+            table.add_row(Row::new(row));
+        }
+
+        table.printstd();
+    }
+}
+
+// Results of the runouts dealing each card, indexed by card.
+#[derive(Debug, Clone)]
+pub(super) struct CardResults {
+    dealt:   u64,
+    before:  EquityResults,
+    results: Vec<EquityResults>,
+}
+
+impl CardResults {
+    pub(super) fn new(dealt: u64, num_players: usize) -> Self {
+        Self {
+            dealt,
+            before:  EquityResults::new(num_players),
+            results: vec![EquityResults::new(num_players); 52],
+        }
+    }
+
+    pub(super) fn board_start(&mut self, results: &EquityResults) {
+        self.before.clone_from(results);
+    }
+
+    // Adds what the board added to the results to each card of its runout.
+    pub(super) fn board_complete(&mut self, board_mask: u64, results: &EquityResults) {
+        let mut runout = board_mask & !self.dealt;
+        while runout != 0 {
+            let card = &mut self.results[runout.trailing_zeros() as usize];
+            runout &= runout - 1;
+
+            for (i, (wins, ties)) in results.wins.iter().zip(results.ties.iter()).enumerate() {
+                card.wins[i] += wins - self.before.wins[i];
+                card.ties[i] += ties - self.before.ties[i];
+            }
+            card.total += results.total - self.before.total;
+        }
+    }
+
+    pub(super) fn merge(&mut self, other: &CardResults) {
+        for (a, b) in self.results.iter_mut().zip(other.results.iter()) {
+            *a = EquityResults::combine(vec![a.clone(), b.clone()]);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{error::Error, prelude::*};
+use super::{is_cancelled, preprocess_ranges, CancelToken, EquityParams, EquityResults, ProgressReporter, Snapshots};
+use super::monte_carlo::{board_mask, has_valid_matchup, showdown, thread_rngs, try_sample_hands, SNAPSHOT_ITERATIONS};
+use rayon::prelude::*;
+
+// Additive steps of the R2 sequence, the 2D generalisation of the golden ratio sequence.
+// Consecutive points are spread evenly over the unit square.
+const R2_ALPHA: [f64; 2] = [0.754_877_666_246_692_7, 0.569_840_290_998_053_2];
+
+// Quasi-Monte Carlo equity.  The first player's combo and the runout are taken from a randomly
+// shifted R2 sequence, so each thread covers combos and runouts evenly rather than at random.
+// The other players' hands are drawn at random, a point where any hands collide is skipped.
+pub fn equity_stratified(equity_params: EquityParams, iterations: Option<u64>) -> Result<EquityResults> {
+
+    let board = equity_params.board.as_vec();
+    let (ranges, deck) = preprocess_ranges(equity_params.ranges, &board)?;
+    if iterations.is_none() && equity_params.cancel.is_none() {
+        return Err(Error::NoStoppingCondition);
+    }
+    if !has_valid_matchup(&ranges, 0) {
+        return Err(Error::NoValidCombos);
+    }
+
+    let params = StratifiedParams {
+        ranges,
+        deck,
+        board,
+        lookup: equity_params.lookup,
+        iterations,
+        reporter: equity_params.reporter,
+        seed: equity_params.seed,
+        cancel: equity_params.cancel,
+    };
+
+    Ok(stratified_runouts(params))
+}
+
+struct StratifiedParams<'a> {
+    ranges:     Vec<Vec<Hand>>,
+    deck:       Deck,
+    board:      Vec<Card>,
+    lookup:     &'a [i32],
+    iterations: Option<u64>,
+    reporter:   Option<&'a dyn ProgressReporter>,
+    seed:       Option<u64>,
+    cancel:     Option<&'a CancelToken>,
+}
+
+fn stratified_runouts(params: StratifiedParams) -> EquityResults {
+
+    let num_threads = rayon::current_num_threads() as u64;
+    let iterations_per_thread = params.iterations.map(|i| i.div_ceil(num_threads));
+    let snapshots = Snapshots::new(params.reporter, num_threads as usize, params.ranges.len());
+
+    let runout_len = 5 - params.board.len();
+    let first_range = &params.ranges[0];
+
+    let results = thread_rngs(params.seed, num_threads).into_par_iter().enumerate().map(|(thread, mut rng)| {
+
+        let mut local_results = EquityResults::new(params.ranges.len());
+        let mut hands = vec![Hand(Card::default(), Card::default()); params.ranges.len()];
+        let mut cards = [Card::default(); 7];
+        cards[2..2 + params.board.len()].copy_from_slice(&params.board);
+        let board_mask = board_mask(&params.board);
+        let mut deck = [Card::default(); 52];
+        let deck = &mut deck[..params.deck.len()];
+        // Index of each card in the deck, for removing the players' cards without a search.
+        let mut deck_positions = [0_u8; 52];
+        for (i, card) in params.deck.into_iter().enumerate() {
+            deck_positions[card.0 as usize] = i as u8;
+        }
+        let mut point = [rng.f64(), rng.f64()];
+        let mut iteration = 0;
+
+        while !is_cancelled(params.cancel) && (iterations_per_thread.is_none() || iteration < iterations_per_thread.unwrap()) {
+
+            for (x, step) in point.iter_mut().zip(R2_ALPHA) {
+                *x += step;
+                if *x >= 1.0 {
+                    *x -= 1.0;
+                }
+            }
+
+            let first = first_range[scale(point[0], first_range.len() as u64) as usize];
+            hands[0] = first;
+            if try_sample_hands(&params.ranges[1..], &mut hands[1..], &mut rng, board_mask | first.mask()).is_none() {
+                continue;
+            }
+
+            deck.copy_from_slice(params.deck.as_ref());
+            let remaining = remove_hands(deck, deck_positions, &hands);
+            draw_runout(point[1], &mut deck[..remaining], &mut cards[7 - runout_len..]);
+
+            showdown(&hands, &mut local_results, &mut cards, params.lookup);
+
+            iteration += 1;
+            if let Some(reporter) = params.reporter {
+                reporter.board_complete();
+            }
+            if let Some(snapshots) = &snapshots {
+                if iteration % SNAPSHOT_ITERATIONS == 0 {
+                    snapshots.update(thread, &local_results);
+                }
+            }
+        }
+
+        local_results
+    }).collect::<Vec<EquityResults>>();
+
+    EquityResults::combine(results)
+}
+
+// Maps a point in [0, 1) to an index in 0..n.
+#[inline]
+fn scale(x: f64, n: u64) -> u64 {
+    ((x * n as f64) as u64).min(n - 1)
+}
+
+// Swaps the players' cards to the end of the deck, returning the number of cards left in front.
+fn remove_hands(deck: &mut [Card], mut positions: [u8; 52], hands: &[Hand]) -> usize {
+    let mut len = deck.len();
+    for card in hands.iter().flat_map(|hand| [hand.0, hand.1]) {
+        len -= 1;
+        let idx = positions[card.0 as usize] as usize;
+        positions[deck[len].0 as usize] = idx as u8;
+        deck.swap(idx, len);
+    }
+    len
+}
+
+// Partial Fisher-Yates shuffle driven by the digits of x in a mixed radix, with the first card
+// as the most significant digit so points close together share their first cards.
+fn draw_runout(mut x: f64, deck: &mut [Card], runout: &mut [Card]) {
+    for (i, slot) in runout.iter_mut().enumerate() {
+        let n = deck.len() - i;
+        x *= n as f64;
+        let j = (x as usize).min(n - 1);
+        x -= j as f64;
+        deck.swap(i, i + j);
+        *slot = deck[i];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draw_runout() {
+        let cards = Deck::new().as_ref()[..10].to_vec();
+        let mut seen = std::collections::HashSet::new();
+
+        // Evenly spaced points draw every ordered runout exactly once.
+        for i in 0..90 {
+            let mut deck = cards.clone();
+            let mut runout = [Card::default(); 2];
+            draw_runout((i as f64 + 0.5) / 90.0, &mut deck, &mut runout);
+            assert_ne!(runout[0], runout[1]);
+            assert!(seen.insert(runout));
+        }
+        assert_eq!(seen.len(), 90);
+    }
+}
@@ -1,5 +1,5 @@
-use crate::{evaluate::load_lookup_table, prelude::*};
-use super::{EquityParams, EquityResults, equity_enumerate, equity_monte_carlo};
+use crate::{error::Error, evaluate::load_lookup_table, prelude::*};
+use super::{calculate_equity, estimate_enumeration_time, CancelToken, EquityMethod, EquityParams, EquityResults, equity_enumerate, equity_histograms, equity_monte_carlo, equity_stratified, flop_equities, runout_equities, weighted_flop_equities, write_flop_csv};
 
 const LOOKUP_PATH: &str = "./data/lookup_table.bin";
 const MC_ITERATIONS: u64 = 100_000;
@@ -52,6 +52,8 @@ fn test_river_heads_up() {
         board: board.clone(),
         lookup: &lookup,
         reporter: None,
+        seed: None,
+        cancel: None,
     };
     let results_enum = equity_enumerate(params_enum).unwrap();
     assert_results_within_margin(&results_enum, vec![56.0, 44.0], 1.0, false);
@@ -61,6 +63,8 @@ fn test_river_heads_up() {
         board,
         lookup: &lookup,
         reporter: None,
+        seed: None,
+        cancel: None,
     };
     let results_mc = equity_monte_carlo(params_mc, Some(MC_ITERATIONS)).unwrap();
     assert_results_within_margin(&results_mc, vec![56.0, 44.0], 5.0, true);
@@ -81,6 +85,8 @@ fn test_river_multi() {
         board: board.clone(),
         lookup: &lookup,
         reporter: None,
+        seed: None,
+        cancel: None,
     };
     let results_enum = equity_enumerate(params_enum).unwrap();
     assert_results_within_margin(&results_enum, vec![13.0, 50.0, 37.0], 1.0, false);
@@ -90,6 +96,8 @@ fn test_river_multi() {
         board,
         lookup: &lookup,
         reporter: None,
+        seed: None,
+        cancel: None,
     };
     let results_mc = equity_monte_carlo(params_mc, Some(MC_ITERATIONS)).unwrap();
     assert_results_within_margin(&results_mc, vec![13.0, 50.0, 37.0], 5.0, true);
@@ -111,6 +119,8 @@ fn test_turn_multi() {
         board: board.clone(),
         lookup: &lookup,
         reporter: None,
+        seed: None,
+        cancel: None,
     };
     let results_enum = equity_enumerate(params_enum).unwrap();
     assert_results_within_margin(&results_enum, vec![32.0, 26.0, 37.0], 1.0, false);
@@ -120,6 +130,8 @@ fn test_turn_multi() {
         board,
         lookup: &lookup,
         reporter: None,
+        seed: None,
+        cancel: None,
     };
     let results_mc = equity_monte_carlo(params_mc, Some(MC_ITERATIONS)).unwrap();
     assert_results_within_margin(&results_mc, vec![32.0, 26.0, 37.0], 5.0, true);
@@ -139,6 +151,8 @@ fn test_flop_heads_up() {
         board: board.clone(),
         lookup: &lookup,
         reporter: None,
+        seed: None,
+        cancel: None,
     };
     let results_enum = equity_enumerate(params_enum).unwrap();
     assert_results_within_margin(&results_enum, vec![66.0, 32.0], 1.0, false);
@@ -148,7 +162,522 @@ fn test_flop_heads_up() {
         board,
         lookup: &lookup,
         reporter: None,
+        seed: None,
+        cancel: None,
     };
     let results_mc = equity_monte_carlo(params_mc, Some(MC_ITERATIONS * 2)).unwrap();
     assert_results_within_margin(&results_mc, vec![66.0, 32.0], 5.0, true);
 }
+
+#[test]
+fn test_monte_carlo_unbiased_multiway() {
+    let lookup = load_lookup_table(LOOKUP_PATH).unwrap();
+    // Heavily overlapping ranges, so the card removal between players matters.
+    let cases = [
+        (vec!["QQ+,AKs", "KK+,AQs+", "AA,KK,AKo"], "Ts 7h 2c 4d"),
+        (vec!["TT+,AKs,AKo", "JJ+", "AQs+,KK+"], "8h 5d 3c"),
+    ];
+
+    for (range_strs, board_str) in cases {
+        let ranges = range_strs.iter().map(|r| Range::from_str(r).unwrap()).collect::<Vec<Range>>();
+        let board = Board::from_str(board_str).unwrap();
+
+        let results_enum = equity_enumerate(EquityParams {
+            ranges: ranges.clone(),
+            board: board.clone(),
+            lookup: &lookup,
+            reporter: None,
+            seed: None,
+            cancel: None,
+        }).unwrap();
+
+        let results_mc = equity_monte_carlo(EquityParams {
+            ranges,
+            board,
+            lookup: &lookup,
+            reporter: None,
+            seed: None,
+            cancel: None,
+        }, Some(MC_ITERATIONS * 5)).unwrap();
+
+        for (i, (mc, exact)) in results_mc.equities().iter().zip(results_enum.equities()).enumerate() {
+            assert!((mc - exact).abs() < 0.5, "Player {} on {}: Monte Carlo equity {:.2}% differs from enumeration {:.2}%", i, board_str, mc, exact);
+        }
+    }
+}
+
+#[test]
+fn test_monte_carlo_no_valid_combos() {
+    let lookup = load_lookup_table(LOOKUP_PATH).unwrap();
+    let ranges = vec![Range::from_str("AA").unwrap(), Range::from_str("AA").unwrap()];
+    let board = Board::from_str("As Kd 7c").unwrap();
+
+    let params = EquityParams {
+        ranges,
+        board,
+        lookup: &lookup,
+        reporter: None,
+        seed: None,
+        cancel: None,
+    };
+    assert!(equity_monte_carlo(params, Some(MC_ITERATIONS)).is_err());
+}
+
+#[test]
+fn test_monte_carlo_seeded() {
+    let lookup = load_lookup_table(LOOKUP_PATH).unwrap();
+    let ranges = vec![
+        Range::from_str("88+,ATs+,KTs+,QJs,AJo+,KQo").unwrap(),
+        Range::from_str("55,K5s,Q7s,98s,A7o,Q9o,J9o").unwrap(),
+    ];
+    let board = Board::from_str("Qh 4h 8c").unwrap();
+
+    let run = |seed| {
+        let params = EquityParams {
+            ranges: ranges.clone(),
+            board: board.clone(),
+            lookup: &lookup,
+            reporter: None,
+            seed: Some(seed),
+            cancel: None,
+        };
+        equity_monte_carlo(params, Some(MC_ITERATIONS)).unwrap()
+    };
+
+    let a = run(42);
+    let b = run(42);
+    assert_eq!(a.wins, b.wins);
+    assert_eq!(a.ties, b.ties);
+    assert_eq!(a.total, b.total);
+
+    let c = run(43);
+    assert_ne!(a.wins, c.wins);
+}
+
+#[test]
+fn test_cancellation() {
+    let lookup = load_lookup_table(LOOKUP_PATH).unwrap();
+    let ranges = vec![
+        Range::from_str("88+,ATs+,KTs+,QJs,AJo+,KQo").unwrap(),
+        Range::from_str("55,K5s,Q7s,98s,A7o,Q9o,J9o").unwrap(),
+    ];
+    let board = Board::from_str("Qh 4h 8c").unwrap();
+
+    // Already cancelled, nothing is enumerated.
+    let cancel = CancelToken::new();
+    cancel.cancel();
+    let results = equity_enumerate(EquityParams {
+        ranges: ranges.clone(),
+        board: board.clone(),
+        lookup: &lookup,
+        reporter: None,
+        seed: None,
+        cancel: Some(&cancel),
+    }).unwrap();
+    assert_eq!(results.total, 0.0);
+
+    // Without an iteration count, Monte Carlo runs until the deadline.
+    let cancel = CancelToken::with_timeout(std::time::Duration::from_millis(100));
+    let results = equity_monte_carlo(EquityParams {
+        ranges: ranges.clone(),
+        board: board.clone(),
+        lookup: &lookup,
+        reporter: None,
+        seed: None,
+        cancel: Some(&cancel),
+    }, None).unwrap();
+    assert!(cancel.is_cancelled());
+    assert!(results.total > 0.0);
+
+    // Neither an iteration count nor a token would never stop.
+    let params = EquityParams {
+        ranges,
+        board,
+        lookup: &lookup,
+        reporter: None,
+        seed: None,
+        cancel: None,
+    };
+    assert!(equity_monte_carlo(params, None).is_err());
+}
+
+#[test]
+fn test_snapshots() {
+    use std::{sync::Mutex, time::Duration};
+    use super::{EquitySnapshot, ProgressReporter};
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<EquitySnapshot>>);
+
+    impl ProgressReporter for Recorder {
+        fn board_complete(&self) {}
+
+        fn snapshot(&self, snapshot: &EquitySnapshot) {
+            self.0.lock().unwrap().push(snapshot.clone());
+        }
+
+        fn snapshot_interval(&self) -> Duration {
+            Duration::ZERO
+        }
+    }
+
+    let lookup = load_lookup_table(LOOKUP_PATH).unwrap();
+    let ranges = vec![
+        Range::from_str("88+,ATs+,KTs+,QJs,AJo+,KQo").unwrap(),
+        Range::from_str("55,K5s,Q7s,98s,A7o,Q9o,J9o").unwrap(),
+    ];
+    let board = Board::from_str("Qh 4h 8c").unwrap();
+
+    let recorder = Recorder::default();
+    let results = equity_enumerate(EquityParams {
+        ranges: ranges.clone(),
+        board: board.clone(),
+        lookup: &lookup,
+        reporter: Some(&recorder),
+        seed: None,
+        cancel: None,
+    }).unwrap();
+
+    let snapshots = recorder.0.lock().unwrap();
+    assert!(!snapshots.is_empty());
+    // The last snapshot is taken after every runout has been published.
+    let last = snapshots.last().unwrap();
+    assert_eq!(last.results.total, results.total);
+    assert_eq!(last.results.wins, results.wins);
+    assert!(snapshots.windows(2).all(|w| w[0].results.total <= w[1].results.total));
+}
+
+
+#[test]
+fn test_stratified_matches_enumerate() {
+    let lookup = load_lookup_table(LOOKUP_PATH).unwrap();
+    let cases = [
+        (vec!["88+,ATs+,KTs+,QJs,AJo+,KQo", "55,K5s,Q7s,98s,A7o,Q9o,J9o"], "Qh 4h 8c"),
+        (vec!["QQ+,AKs", "KK+,AQs+", "AA,KK,AKo"], "Ts 7h 2c 4d"),
+        (vec!["66+,A8s+,KTs+,QTs+,JTs,ATo+,KJo+", "22-99,A2o-A8o"], "Qh 4h 8c Qc 6s"),
+    ];
+
+    for (range_strs, board_str) in cases {
+        let ranges = range_strs.iter().map(|r| Range::from_str(r).unwrap()).collect::<Vec<Range>>();
+        let board = Board::from_str(board_str).unwrap();
+
+        let results_enum = equity_enumerate(EquityParams {
+            ranges: ranges.clone(),
+            board: board.clone(),
+            lookup: &lookup,
+            reporter: None,
+            seed: None,
+            cancel: None,
+        }).unwrap();
+
+        let results_stratified = equity_stratified(EquityParams {
+            ranges,
+            board,
+            lookup: &lookup,
+            reporter: None,
+            seed: None,
+            cancel: None,
+        }, Some(MC_ITERATIONS * 5)).unwrap();
+
+        for (i, (qmc, exact)) in results_stratified.equities().iter().zip(results_enum.equities()).enumerate() {
+            assert!((qmc - exact).abs() < 0.5, "Player {} on {}: stratified equity {:.2}% differs from enumeration {:.2}%", i, board_str, qmc, exact);
+        }
+    }
+}
+
+#[test]
+fn test_calculate_equity_auto() {
+    use std::time::{Duration, Instant};
+
+    let lookup = load_lookup_table(LOOKUP_PATH).unwrap();
+    let ranges = vec![
+        Range::from_str("66+,A8s+,KTs+,QTs+,JTs,ATo+,KJo+").unwrap(),
+        Range::from_str("22-99,A2o-A8o").unwrap(),
+    ];
+    let params = |board: &str| EquityParams {
+        ranges: ranges.clone(),
+        board: Board::from_str(board).unwrap(),
+        lookup: &lookup,
+        reporter: None,
+        seed: None,
+        cancel: None,
+    };
+
+    // A river is cheap enough to enumerate.
+    let exact = equity_enumerate(params("Qh 4h 8c Qc 6s")).unwrap();
+    let auto = calculate_equity(params("Qh 4h 8c Qc 6s"), EquityMethod::Auto(Duration::from_secs(1))).unwrap();
+    assert_eq!(auto.total, exact.total);
+    assert_eq!(auto.wins, exact.wins);
+
+    // Preflop is not, so it samples until the budget runs out.
+    let start = Instant::now();
+    let auto = calculate_equity(params(""), EquityMethod::Auto(Duration::from_millis(200))).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert!(auto.total > 0.0);
+}
+
+#[test]
+fn test_auto_many_wide_ranges() {
+    use std::time::Duration;
+
+    // Enumerating seven full ranges would take longer than a Duration holds.
+    let ranges = vec![Range::new_from_grid(vec![true; 169]); 7];
+    assert_eq!(estimate_enumeration_time(&ranges, &Board::default()), Duration::MAX);
+
+    let lookup = load_lookup_table(LOOKUP_PATH).unwrap();
+    let params = EquityParams { ranges, board: Board::default(), lookup: &lookup, reporter: None, seed: None, cancel: None };
+    let auto = calculate_equity(params, EquityMethod::Auto(Duration::from_millis(100))).unwrap();
+    assert!(auto.total > 0.0);
+}
+
+#[test]
+fn test_equity_histograms() {
+    let lookup = load_lookup_table(LOOKUP_PATH).unwrap();
+    let params = |ranges: Vec<Range>, board: &str| EquityParams {
+        ranges,
+        board: Board::from_str(board).unwrap(),
+        lookup: &lookup,
+        reporter: None,
+        seed: None,
+        cancel: None,
+    };
+
+    // On the river every combo has a single strength.
+    let ranges = vec![Range::from_str("AA").unwrap(), Range::from_str("KK").unwrap()];
+    let histograms = equity_histograms(params(ranges, "2c 7d 9s Jh 3c"), 10).unwrap();
+    assert_eq!(histograms[0].len(), 6);
+    for strength in &histograms[0] {
+        assert_eq!(strength.runouts, 1.0);
+        assert_eq!(strength.ehs, 1.0);
+        assert_eq!(strength.histogram[9], 1.0);
+    }
+    assert!(histograms[1].iter().all(|strength| strength.ehs == 0.0 && strength.histogram[0] == 1.0));
+
+    // With one combo each there is one matchup per runout, so E[HS] is the pot share over the runouts.
+    let single = |hand: &str| {
+        let mut range = Range::default();
+        range.set_hand(&Hand::from_str(hand).unwrap(), true);
+        range
+    };
+    let ranges = vec![single("AsAh"), single("KsKh")];
+    let exact = equity_enumerate(params(ranges.clone(), "2c 7d 9s")).unwrap();
+    let histograms = equity_histograms(params(ranges, "2c 7d 9s"), 20).unwrap();
+    for (player, strengths) in histograms.iter().enumerate() {
+        let strength = &strengths[0];
+        assert_eq!(strength.runouts, exact.total);
+        assert!((strength.ehs - (exact.wins[player] + exact.ties[player]) / exact.total).abs() < 1e-9);
+        assert!(strength.ehs2 >= strength.ehs * strength.ehs);
+        assert!((strength.histogram.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn test_runout_equities() {
+    let lookup = load_lookup_table(LOOKUP_PATH).unwrap();
+    let ranges = vec![
+        Range::from_str("88+,ATs+,KTs+,QJs,AJo+,KQo").unwrap(),
+        Range::from_str("55,K5s,Q7s,98s,A7o,Q9o,J9o").unwrap(),
+    ];
+    let params = |board: &str| EquityParams {
+        ranges: ranges.clone(),
+        board: Board::from_str(board).unwrap(),
+        lookup: &lookup,
+        reporter: None,
+        seed: None,
+        cancel: None,
+    };
+
+    let runouts = runout_equities(params("Qh 4h 8c")).unwrap();
+    assert_eq!(runouts.cards.len(), 49);
+    // Each runout has a turn and a river card.
+    assert_eq!(runouts.cards.iter().map(|(_, results)| results.total).sum::<f64>(), 2.0 * runouts.total.total);
+
+    // A turn card's results are those of enumerating the turn board.
+    for (card, results) in runouts.cards.iter().step_by(7) {
+        let mut board = Card::vec_from_str("Qh 4h 8c").unwrap();
+        board.push(*card);
+        let turn = equity_enumerate(EquityParams { board: Board::from_vec(board).unwrap(), ..params("") }).unwrap();
+        assert_eq!(results.total, turn.total);
+        for (a, b) in results.equities().iter().zip(turn.equities()) {
+            assert!((a - b).abs() < 1e-9, "{}: {} vs {}", card, a, b);
+        }
+    }
+
+    assert!(runout_equities(params("Qh 4h 8c 2s 3s")).is_err());
+}
+
+#[test]
+fn test_flop_equities() {
+    let lookup = load_lookup_table(LOOKUP_PATH).unwrap();
+    let ranges = vec![Range::from_str("AA").unwrap(), Range::from_str("KK,QQ").unwrap()];
+
+    let flops = flop_equities(&ranges, &lookup, None).unwrap();
+    assert_eq!(flops.len(), 1755);
+    assert_eq!(flops.iter().map(|flop| flop.weight).sum::<u32>(), 22_100);
+
+    // Each flop matches enumerating it on its own.
+    let flop = &flops[100];
+    let results = equity_enumerate(EquityParams {
+        ranges: ranges.clone(),
+        board: flop.board.clone(),
+        lookup: &lookup,
+        reporter: None,
+        seed: None,
+        cancel: None,
+    }).unwrap();
+    assert_eq!(flop.results.total, results.total);
+
+    // Weighting by flop counts ignores blockers, so it is only near the preflop equity.
+    let equities = weighted_flop_equities(&flops);
+    assert!((equities[0] - 81.5).abs() < 5.0, "{:?}", equities);
+
+    let mut csv = Vec::new();
+    write_flop_csv(&flops, &["AA".to_string(), "KK,QQ".to_string()], &mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert_eq!(csv.lines().count(), 1756);
+    assert!(csv.starts_with("flop,weight,\"AA\",\"KK,QQ\",suitedness"));
+    assert!(csv.lines().nth(1).unwrap().starts_with("AsAhAd,4,"));
+
+    let mut suited = Range::default();
+    suited.set_hand(&Hand::from_str("Ah Kh").unwrap(), true);
+    assert!(flop_equities(&[ranges[0].clone(), suited], &lookup, None).is_err());
+
+    // Mirrored ranges chop most boards and split the equity evenly.
+    let mirrored = [ranges[0].clone(), ranges[0].clone()];
+    let flops = flop_equities(&mirrored, &lookup, None).unwrap();
+    let equities = weighted_flop_equities(&flops);
+    assert!((equities[0] + equities[1] - 100.0).abs() < 1e-9, "{:?}", equities);
+    assert!((equities[0] - 50.0).abs() < 1.0, "{:?}", equities);
+    let csv = {
+        let mut csv = Vec::new();
+        write_flop_csv(&flops, &["AA".to_string(), "AA".to_string()], &mut csv).unwrap();
+        String::from_utf8(csv).unwrap()
+    };
+    let row = csv.lines().last().unwrap().split(',').collect::<Vec<&str>>();
+    let sum = row[2].parse::<f64>().unwrap() + row[3].parse::<f64>().unwrap();
+    assert!((sum - 100.0).abs() < 0.01, "{:?}", row);
+}
+
+#[test]
+fn test_preflop_equities() {
+    use super::{hand_class, hand_class_combos, hand_class_name, preflop_class_equity, PreflopEquities, NUM_CLASSES};
+
+    assert_eq!(hand_class(&Hand::from_str("As Ks").unwrap()), 1);
+    assert_eq!(hand_class(&Hand::from_str("Kd Ac").unwrap()), 13);
+    assert_eq!(hand_class_name(1), "AKs");
+    assert_eq!(hand_class_name(13), "AKo");
+    assert_eq!(hand_class_name(168), "22");
+    let total = (0..NUM_CLASSES).map(|class| {
+        let combos = hand_class_combos(class);
+        assert!(combos.iter().all(|hand| hand_class(hand) == class));
+        combos.len()
+    }).sum::<usize>();
+    assert_eq!(total, 1326);
+
+    let lookup = load_lookup_table(LOOKUP_PATH).unwrap();
+    let equity = preflop_class_equity(0, 14, &lookup).unwrap();
+    assert!((equity - 0.8195).abs() < 0.001, "{}", equity);
+
+    // Equities only need to hold the row class's side, the pairs come from the classes.
+    let mut equities = vec![0.5; NUM_CLASSES * NUM_CLASSES];
+    equities[14] = equity;
+    equities[14 * NUM_CLASSES] = 1.0 - equity;
+    let table = PreflopEquities::from_vec(equities);
+    assert_eq!(table.combo_pairs(0, 14), 36.0);
+    assert_eq!(table.combo_pairs(0, 0), 6.0);
+
+    let mut kings = vec![0.0; NUM_CLASSES];
+    kings[14] = 1.0;
+    let (vs_kings, combos) = table.equity_vs_range(0, &kings);
+    assert_eq!(vs_kings, equity);
+    assert_eq!(combos, 6.0);
+
+    let path = std::env::temp_dir().join("preflop_equities_test.bin");
+    table.save(&path).unwrap();
+    let loaded = PreflopEquities::load(&path).unwrap();
+    assert_eq!(loaded.equity(14, 0), 1.0 - equity);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_river_analysis() {
+    use super::{river_analysis, RiverAction};
+
+    let lookup = load_lookup_table(LOOKUP_PATH).unwrap();
+    let board = Board::from_str("As Kd 7c 4h 2s").unwrap();
+    let betting = Range::from_str("AA, KK, 77, AQs, QJs, T9s, 65s").unwrap();
+    let calling = Range::from_str("QQ, AKs, AKo, AQs, AQo, KQs, KQo").unwrap();
+
+    let analysis = river_analysis(&betting, &calling, &board, 1.0, &lookup).unwrap();
+    assert_eq!(analysis.mdf, 0.5);
+    assert_eq!(analysis.alpha, 0.5);
+    // Half of the 39 calling combos the board leaves, rounded up.
+    assert_eq!(analysis.defending.len(), 20);
+
+    // Sets value bet, half as many of the missed draws bluff and top pair checks.
+    assert_eq!(analysis.value_combos(), 9);
+    assert_eq!(analysis.bluff_combos(), 5);
+    for combo in &analysis.combos {
+        let high = combo.hand.high_card().to_string();
+        match combo.action {
+            RiverAction::Value => assert!(combo.hand.pocket_pair()),
+            RiverAction::Bluff => assert!(["Q", "T", "6"].contains(&high.as_str())),
+            RiverAction::Check => assert!(high == "A" || !combo.hand.pocket_pair()),
+        }
+    }
+    assert!(analysis.combos.iter().any(|combo| combo.hand.high_card().to_string() == "A" && combo.action == RiverAction::Check));
+
+    let turn = Board::from_str("As Kd 7c 4h").unwrap();
+    assert!(river_analysis(&betting, &calling, &turn, 1.0, &lookup).is_err());
+    for bet in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        assert!(matches!(river_analysis(&betting, &calling, &board, bet, &lookup), Err(Error::InvalidBetSize(_))));
+    }
+}
+
+#[test]
+fn test_equity_distribution() {
+    use super::equity_distribution;
+
+    let lookup = load_lookup_table(LOOKUP_PATH).unwrap();
+    let params = |board: &str| EquityParams {
+        ranges: vec![Range::from_str("AA, KK, 99, AKs").unwrap(), Range::from_str("QQ, JJ, 76s").unwrap()],
+        board: Board::from_str(board).unwrap(),
+        lookup: &lookup,
+        reporter: None,
+        seed: None,
+        cancel: None,
+    };
+    let distribution = equity_distribution(params("Ks 9d 4c 2h"), 2).unwrap();
+
+    // Sets of kings and nines are the two strongest hands on the turn.
+    assert_eq!(distribution.combos(0), 6 + 3 + 3 + 3);
+    assert_eq!(distribution.nuts, vec![6, 0]);
+    assert_eq!(distribution.nut_share(0), 0.4);
+    assert!(distribution.share_above(0, 0.8) > distribution.share_above(1, 0.8));
+    assert_eq!(distribution.cdf(0, 1.0), 1.0);
+    assert!(distribution.equities[0].windows(2).all(|pair| pair[0] <= pair[1]));
+
+    let mut csv = Vec::new();
+    distribution.write_csv(&["a".to_string(), "b".to_string()], &mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert_eq!(csv.lines().count(), 102);
+    assert!(csv.lines().last().unwrap().starts_with("100,1.0000,1.0000"));
+
+    assert!(equity_distribution(params(""), 2).is_err());
+}
+
+#[test]
+fn test_results_equities() {
+    // Ties hold each player's share of the chopped pots.
+    let results = EquityResults { wins: vec![0.25, 0.25], ties: vec![0.25, 0.25], total: 1.0 };
+    assert_eq!(results.equities(), [50.0, 50.0]);
+}
+
+#[test]
+fn test_results_serde() {
+    let results = EquityResults { wins: vec![0.75, 0.2], ties: vec![0.025, 0.025], total: 1.0 };
+    let json = serde_json::to_string(&results).unwrap();
+    assert_eq!(json, r#"{"wins":[0.75,0.2],"ties":[0.025,0.025],"total":1.0}"#);
+    let parsed = serde_json::from_str::<EquityResults>(&json).unwrap();
+    assert_eq!((parsed.wins, parsed.ties, parsed.total), (results.wins, results.ties, results.total));
+}
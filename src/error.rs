@@ -15,9 +15,52 @@ pub enum Error {
     #[error("Error loading lookup table: {0}")]
     LookupTableError(#[from] std::io::Error),
 
+    #[error("Error loading preflop equity table: {0}")]
+    PreflopTableError(std::io::Error),
+
     #[error("Could not find lookup table at provided path, generate with the generate_lookup binary")]
     LookupTableNotFound,
 
+    #[error("No valid combination of hands for the given ranges and board")]
+    NoValidCombos,
+
+    #[error("Runout equities need a flop or turn board, got {0} cards")]
+    InvalidRunoutBoard(usize),
+
+    #[error("River analysis needs a full board, got {0} cards")]
+    InvalidRiverBoard(usize),
+
+    #[error("Bet size must be a positive fraction of the pot, got {0}")]
+    InvalidBetSize(f64),
+
+    #[error("Nut combos need a flop, turn or river board, got {0} cards")]
+    InvalidNutBoard(usize),
+
+    // Numbered from 1 in the order given.
+    #[error("Range {0} is not suit symmetric, flop reports stand each canonical flop in for its isomorphic flops")]
+    AsymmetricRange(usize),
+
+    #[error("Game trees need a flop, turn or river board, got {0} cards")]
+    InvalidTreeBoard(usize),
+
+    #[error("Invalid game tree: {0}")]
+    InvalidTree(&'static str),
+
+    #[error("Game tree would have {0} nodes, more than the limit of {1}")]
+    TreeTooLarge(u64, u64),
+
+    #[error("Push/fold needs 2 to 10 players, got {0}")]
+    InvalidPlayerCount(usize),
+
+    #[error("Invalid ICM spot: {0}")]
+    InvalidIcmSpot(&'static str),
+
+    #[error("Invalid EV spot: {0}")]
+    InvalidEvSpot(&'static str),
+
+    #[error("Monte Carlo simulation needs an iteration count or a cancellation token to stop")]
+    NoStoppingCondition,
+
     #[error("No lookup path set, should have been generated at build, or set existing env variable POKER_LOOKUP_TABLE_PATH")]
     LookupPathNotSet,
 }
@@ -0,0 +1,180 @@
+use crate::{equity::{equity_enumerate, EquityParams, EquityResults}, error::{Error, Result}, prelude::*};
+
+// Share of each pot the house keeps, up to the cap.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rake {
+    pub rate: f64,
+    pub cap:  Option<f64>,
+}
+
+impl Rake {
+    pub fn take(&self, pot: f64) -> f64 {
+        let rake = pot * self.rate;
+        self.cap.map_or(rake, |cap| rake.min(cap))
+    }
+}
+
+// Hero putting chips in heads-up, calling a bet or jamming.  EVs are in chips relative to folding,
+// what is already in the pot is sunk, so folding is always worth 0.
+#[derive(Debug, Clone)]
+pub struct EvSpot {
+    // Chips in the middle before hero acts, any bet hero faces included.
+    pub pot:  f64,
+    // Chips hero puts in.
+    pub bet:  f64,
+    // Chips the villain puts in to call hero's bet, 0 when hero is the one calling.
+    pub call: f64,
+    pub rake: Rake,
+}
+
+#[derive(Debug, Clone)]
+pub struct EvDecision {
+    // Hero's share of the pot at showdown.
+    pub equity:            f64,
+    pub break_even_equity: f64,
+    // Hero's EV when the hand goes to showdown, and overall counting the villain's folds.
+    pub showdown_ev:       f64,
+    pub ev:                f64,
+    // Chips hero takes down when the villain folds, times how often they fold.
+    pub fold_equity:       f64,
+    // How often the villain must fold for the bet to break even with hero's equity.
+    pub break_even_fold:   f64,
+}
+
+impl EvDecision {
+    pub fn profitable(&self) -> bool {
+        self.ev > 0.0
+    }
+}
+
+impl EvSpot {
+    fn check(&self, fold_frequency: f64) -> Result<()> {
+        if self.pot < 0.0 || self.bet <= 0.0 || self.call < 0.0 {
+            return Err(Error::InvalidEvSpot("pot and call must not be negative, and the bet must be positive"));
+        }
+        if !(0.0..=1.0).contains(&self.rake.rate) || self.rake.cap.is_some_and(|cap| cap < 0.0) {
+            return Err(Error::InvalidEvSpot("rake must be between 0 and 1 with a cap that is not negative"));
+        }
+        if !(0.0..=1.0).contains(&fold_frequency) {
+            return Err(Error::InvalidEvSpot("fold frequency must be between 0 and 1"));
+        }
+        // Calling closes the action, there is nothing left for the villain to fold to.
+        if self.call == 0.0 && fold_frequency > 0.0 {
+            return Err(Error::InvalidEvSpot("the villain can't fold when hero is calling"));
+        }
+        Ok(())
+    }
+
+    // Pot after rake when the villain calls, and when they fold.
+    fn called_pot(&self) -> f64 {
+        let pot = self.pot + self.bet + self.call;
+        pot - self.rake.take(pot)
+    }
+
+    fn folded_pot(&self) -> f64 {
+        self.pot - self.rake.take(self.pot)
+    }
+
+    pub fn showdown_ev(&self, equity: f64) -> f64 {
+        equity * self.called_pot() - self.bet
+    }
+
+    pub fn break_even_equity(&self) -> f64 {
+        self.bet / self.called_pot()
+    }
+
+    pub fn ev(&self, equity: f64, fold_frequency: f64) -> f64 {
+        fold_frequency * self.folded_pot() + (1.0 - fold_frequency) * self.showdown_ev(equity)
+    }
+
+    pub fn break_even_fold(&self, equity: f64) -> f64 {
+        let showdown = self.showdown_ev(equity);
+        if showdown >= 0.0 {
+            return 0.0;
+        }
+        -showdown / (self.folded_pot() - showdown)
+    }
+
+    // Hero's EVs given the results of hero's range against the villain's and how often the villain
+    // folds, 0 when hero is calling.
+    pub fn decide(&self, results: &EquityResults, fold_frequency: f64) -> Result<EvDecision> {
+        self.check(fold_frequency)?;
+        let equity = (results.wins[0] + results.ties[0]) / results.total;
+        Ok(EvDecision {
+            equity,
+            break_even_equity: self.break_even_equity(),
+            showdown_ev:       self.showdown_ev(equity),
+            ev:                self.ev(equity, fold_frequency),
+            fold_equity:       fold_frequency * self.folded_pot(),
+            break_even_fold:   self.break_even_fold(equity),
+        })
+    }
+}
+
+// Enumerates hero's range against the villain's on the board and decides the spot.
+pub fn ev_all_in(spot: &EvSpot, ranges: [Range; 2], board: Board, lookup: &[i32], fold_frequency: f64) -> Result<EvDecision> {
+    spot.check(fold_frequency)?;
+    let results = equity_enumerate(EquityParams {
+        ranges:   ranges.to_vec(),
+        board,
+        lookup,
+        reporter: None,
+        seed:     None,
+        cancel:   None,
+    })?;
+    spot.decide(&results, fold_frequency)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} vs {}", a, b);
+    }
+
+    #[test]
+    fn test_ev_call() {
+        // Calling 50 into 150 needs a quarter of the pot.
+        let spot = EvSpot { pot: 150.0, bet: 50.0, call: 0.0, rake: Rake::default() };
+        assert_close(spot.break_even_equity(), 0.25);
+        assert_close(spot.showdown_ev(0.25), 0.0);
+        assert_close(spot.showdown_ev(0.5), 50.0);
+
+        // Rake raises the equity needed, until it hits the cap.
+        let raked = EvSpot { rake: Rake { rate: 0.05, cap: None }, ..spot.clone() };
+        assert_close(raked.break_even_equity(), 50.0 / 190.0);
+        let capped = EvSpot { rake: Rake { rate: 0.05, cap: Some(3.0) }, ..spot.clone() };
+        assert_close(capped.break_even_equity(), 50.0 / 197.0);
+
+        let results = EquityResults { wins: vec![0.3, 0.6], ties: vec![0.05, 0.05], total: 1.0 };
+        let decision = spot.decide(&results, 0.0).unwrap();
+        assert_close(decision.equity, 0.35);
+        assert_close(decision.ev, 20.0);
+        assert!(decision.profitable());
+        assert!(spot.decide(&results, 1.5).is_err());
+        assert!(matches!(spot.decide(&results, 0.5), Err(Error::InvalidEvSpot(_))));
+    }
+
+    #[test]
+    fn test_ev_jam() {
+        // Jamming 100 into 15 with 30% when called needs the villain to fold often.
+        let spot = EvSpot { pot: 15.0, bet: 100.0, call: 92.5, rake: Rake::default() };
+        assert_close(spot.showdown_ev(0.3), 0.3 * 207.5 - 100.0);
+        let fold = spot.break_even_fold(0.3);
+        assert_close(spot.ev(0.3, fold), 0.0);
+        assert!(spot.ev(0.3, fold + 0.01) > 0.0);
+        assert_close(spot.break_even_fold(0.6), 0.0);
+
+        let results = EquityResults { wins: vec![0.3, 0.7], ties: vec![0.0, 0.0], total: 1.0 };
+        let decision = spot.decide(&results, 0.5).unwrap();
+        assert_close(decision.fold_equity, 7.5);
+        assert_close(decision.ev, 7.5 + 0.5 * decision.showdown_ev);
+
+        let lookup = load_lookup_table("./data/lookup_table.bin").unwrap();
+        let ranges = [Range::from_str("AA").unwrap(), Range::from_str("KK").unwrap()];
+        let decision = ev_all_in(&spot, ranges, Board::default(), &lookup, 0.0).unwrap();
+        assert!((decision.equity - 0.8195).abs() < 0.001);
+        assert!(decision.profitable());
+    }
+}
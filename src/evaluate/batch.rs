@@ -0,0 +1,294 @@
+use crate::{card::Card, hand::Hand};
+use super::{BoardState, HandRank};
+
+// Batched 7-card evaluation, for many hole card pairs that share the same 5-card board.
+
+// The bit evaluator written branch free over lanes, so it runs on plain u32s or on SIMD vectors.
+// Each lane holds one hand, the ops module provides the lane operations, with comparisons
+// returning all ones or all zeros.
+macro_rules! rank_lanes {
+    ($ops:ident, $board:expr, $ra:expr, $rb:expr, $sa:expr, $sb:expr) => {{
+        use $ops::*;
+
+        let one = splat(1);
+        let zero = splat(0);
+        let (bit_a, bit_b) = (sllv(one, $ra), sllv(one, $rb));
+
+        // Add the hole cards to the board counts.
+        let counts = $board.counts;
+        let [mut c1, mut c2, mut c3, mut c4] = [splat(counts[0]), splat(counts[1]), splat(counts[2]), splat(counts[3])];
+        for bit in [bit_a, bit_b] {
+            c4 = or(c4, and(c3, bit));
+            c3 = or(c3, and(c2, bit));
+            c2 = or(c2, and(c1, bit));
+            c1 = or(c1, bit);
+        }
+        let rank_set = c1;
+        let quads = c4;
+        let trips = andnot(c3, c4);
+        let pairs = andnot(c2, c3);
+
+        let flush_suit = splat($board.flush_suit);
+        let flush_set = or(
+            splat($board.flush_set),
+            or(and(bit_a, eq($sa, flush_suit)), and(bit_b, eq($sb, flush_suit))),
+        );
+
+        // Keep the n most significant bits, at most 2 ever need dropping from 7 cards.
+        let n_msb = |x, n| {
+            let mut x = x;
+            for _ in 0..2 {
+                x = select(gt(popcount(x), splat(n)), and(x, sub(x, one)), x);
+            }
+            x
+        };
+        let msb = |x| {
+            let mut x = x;
+            for s in [1, 2, 4, 8] {
+                x = or(x, shr(x, s));
+            }
+            xor(x, shr(x, 1))
+        };
+        // Straight index as in bits::find_straight, with a mask of lanes that have one.
+        let straight = |x| {
+            let left = and(and(and(x, shl(x, 1)), and(shl(x, 2), shl(x, 3))), shl(x, 4));
+            let wheel = eq(and(x, splat(0b1_0000_0000_1111)), splat(0b1_0000_0000_1111));
+            let found = not(eq(left, zero));
+            let idx = sub(popcount(sub(msb(left), one)), splat(3));
+            (select(found, idx, zero), or(found, wheel))
+        };
+        let category = |c: u32, value| or(splat(c << CATEGORY_SHIFT), value);
+
+        let mut rank = category(1, or(shl(pairs, 13), n_msb(xor(rank_set, pairs), 3)));
+        rank = select(eq(pairs, zero), category(0, n_msb(rank_set, 5)), rank);
+
+        let top_pairs = n_msb(pairs, 2);
+        let two_pair = category(2, or(shl(top_pairs, 13), msb(xor(rank_set, top_pairs))));
+        rank = select(gt(popcount(pairs), one), two_pair, rank);
+
+        let three_of_a_kind = category(3, or(shl(trips, 13), n_msb(xor(rank_set, trips), 2)));
+        rank = select(not(eq(trips, zero)), three_of_a_kind, rank);
+
+        let (straight_idx, is_straight) = straight(rank_set);
+        rank = select(is_straight, category(4, straight_idx), rank);
+
+        let has_trips_and_pair = and(not(eq(trips, zero)), not(eq(pairs, zero)));
+        rank = select(has_trips_and_pair, category(6, or(shl(trips, 13), msb(pairs))), rank);
+        let top_trips = msb(trips);
+        let two_trips = category(6, or(shl(top_trips, 13), xor(trips, top_trips)));
+        rank = select(gt(popcount(trips), one), two_trips, rank);
+
+        let four_of_a_kind = category(7, or(shl(quads, 13), msb(xor(rank_set, quads))));
+        rank = select(not(eq(quads, zero)), four_of_a_kind, rank);
+
+        let (straight_flush_idx, is_straight_flush) = straight(flush_set);
+        let flush = select(is_straight_flush, category(8, straight_flush_idx), category(5, n_msb(flush_set, 5)));
+        rank = select(gt(popcount(flush_set), splat(4)), flush, rank);
+
+        rank
+    }};
+}
+
+// Two plus two evaluation of each hand on a 5 card board state, two lookups per hand.
+pub fn eval_7_2p2_batch(board: &BoardState, hands: &[Hand], lookup_table: &[i32], ranks: &mut [u16]) {
+    assert!(board.num_cards() == 5);
+    assert!(ranks.len() >= hands.len());
+
+    for (hand, rank) in hands.iter().zip(ranks.iter_mut()) {
+        *rank = board.eval_hand(hand, lookup_table);
+    }
+}
+
+// Bit evaluation of each hand on the board, giving the same ranks as rank_hand_bits.
+// Evaluates 8 hands at a time with AVX2 where available, otherwise one at a time.
+pub fn rank_hands_bits_batch(board: &[Card], hands: &[Hand], ranks: &mut [HandRank]) {
+    assert!(board.len() == 5);
+    assert!(ranks.len() >= hands.len());
+
+    let board = BoardBits::new(board);
+
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") {
+        // Safety: AVX2 support was checked above.
+        unsafe { avx2::rank_hands(&board, hands, ranks) };
+        return;
+    }
+
+    rank_hands_scalar(&board, hands, ranks);
+}
+
+fn rank_hands_scalar(board: &BoardBits, hands: &[Hand], ranks: &mut [HandRank]) {
+    for (hand, rank) in hands.iter().zip(ranks.iter_mut()) {
+        let (ra, rb) = (hand.0.rank_u8() as u32, hand.1.rank_u8() as u32);
+        let (sa, sb) = (hand.0.suit_u8() as u32, hand.1.suit_u8() as u32);
+        *rank = unpack(rank_lanes!(scalar, board, ra, rb, sa, sb));
+    }
+}
+
+// Board cards as per-rank count sets, ranks with at least 1, 2, 3 and 4 cards.  Only a suit with
+// 3 or more cards on the board can make a flush.
+struct BoardBits {
+    counts:     [u32; 4],
+    flush_suit: u32,
+    flush_set:  u32,
+}
+
+impl BoardBits {
+    fn new(board: &[Card]) -> Self {
+        let mut counts = [0_u32; 4];
+        let mut suit_sets = [0_u32; 4];
+
+        for card in board {
+            let bit = 1 << card.rank_u8();
+            counts[3] |= counts[2] & bit;
+            counts[2] |= counts[1] & bit;
+            counts[1] |= counts[0] & bit;
+            counts[0] |= bit;
+            suit_sets[card.suit_u8() as usize] |= bit;
+        }
+
+        // With no suit possible, the flush suit is out of range so never matches a hole card.
+        let flush_suit = suit_sets.iter().position(|set| set.count_ones() >= 3);
+        Self {
+            counts,
+            flush_suit: flush_suit.map_or(4, |s| s as u32),
+            flush_set:  flush_suit.map_or(0, |s| suit_sets[s]),
+        }
+    }
+}
+
+// Category in the top bits, with the same value as HandRank below, so packed ranks compare
+// the same way as HandRank.
+const CATEGORY_SHIFT: u32 = 26;
+
+fn unpack(packed: u32) -> HandRank {
+    let value = packed & ((1 << CATEGORY_SHIFT) - 1);
+    match packed >> CATEGORY_SHIFT {
+        0 => HandRank::HighCard(value),
+        1 => HandRank::Pair(value),
+        2 => HandRank::TwoPair(value),
+        3 => HandRank::ThreeOfAKind(value),
+        4 => HandRank::Straight(value),
+        5 => HandRank::Flush(value),
+        6 => HandRank::FullHouse(value),
+        7 => HandRank::FourOfAKind(value),
+        8 => HandRank::StraightFlush(value),
+        _ => unreachable!(),
+    }
+}
+
+mod scalar {
+    #[inline(always)] pub fn splat(x: u32) -> u32 { x }
+    #[inline(always)] pub fn and(a: u32, b: u32) -> u32 { a & b }
+    #[inline(always)] pub fn or(a: u32, b: u32) -> u32 { a | b }
+    #[inline(always)] pub fn xor(a: u32, b: u32) -> u32 { a ^ b }
+    #[inline(always)] pub fn not(a: u32) -> u32 { !a }
+    // a & !b
+    #[inline(always)] pub fn andnot(a: u32, b: u32) -> u32 { a & !b }
+    #[inline(always)] pub fn shl(a: u32, n: i32) -> u32 { a << n }
+    #[inline(always)] pub fn shr(a: u32, n: i32) -> u32 { a >> n }
+    #[inline(always)] pub fn sllv(a: u32, n: u32) -> u32 { a << n }
+    #[inline(always)] pub fn sub(a: u32, b: u32) -> u32 { a.wrapping_sub(b) }
+    #[inline(always)] pub fn eq(a: u32, b: u32) -> u32 { if a == b { !0 } else { 0 } }
+    #[inline(always)] pub fn gt(a: u32, b: u32) -> u32 { if a > b { !0 } else { 0 } }
+    #[inline(always)] pub fn select(mask: u32, a: u32, b: u32) -> u32 { (mask & a) | (!mask & b) }
+    #[inline(always)] pub fn popcount(a: u32) -> u32 { a.count_ones() }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use std::arch::x86_64::*;
+    use crate::hand::Hand;
+    use super::{unpack, BoardBits, HandRank, CATEGORY_SHIFT};
+
+    #[target_feature(enable = "avx2")]
+    pub(super) fn rank_hands(board: &BoardBits, hands: &[Hand], ranks: &mut [HandRank]) {
+        for (hands, ranks) in hands.chunks(8).zip(ranks.chunks_mut(8)) {
+            // Unused lanes repeat the first hand.
+            let lane = |f: fn(&Hand) -> u8| {
+                let mut values = [f(&hands[0]) as i32; 8];
+                hands.iter().zip(values.iter_mut()).for_each(|(hand, v)| *v = f(hand) as i32);
+                values
+            };
+            let load = |values: [i32; 8]| _mm256_setr_epi32(
+                values[0], values[1], values[2], values[3], values[4], values[5], values[6], values[7],
+            );
+            let ra = load(lane(|h| h.0.rank_u8()));
+            let rb = load(lane(|h| h.1.rank_u8()));
+            let sa = load(lane(|h| h.0.suit_u8()));
+            let sb = load(lane(|h| h.1.suit_u8()));
+
+            let packed = rank_lanes!(ops, board, ra, rb, sa, sb);
+            // Safety: __m256i and [u32; 8] have the same size and any bit pattern is valid.
+            let packed: [u32; 8] = unsafe { std::mem::transmute(packed) };
+            ranks.iter_mut().zip(packed).for_each(|(rank, p)| *rank = unpack(p));
+        }
+    }
+
+    mod ops {
+        use std::arch::x86_64::*;
+
+        #[target_feature(enable = "avx2")] #[inline] pub fn splat(x: u32) -> __m256i { _mm256_set1_epi32(x as i32) }
+        #[target_feature(enable = "avx2")] #[inline] pub fn and(a: __m256i, b: __m256i) -> __m256i { _mm256_and_si256(a, b) }
+        #[target_feature(enable = "avx2")] #[inline] pub fn or(a: __m256i, b: __m256i) -> __m256i { _mm256_or_si256(a, b) }
+        #[target_feature(enable = "avx2")] #[inline] pub fn xor(a: __m256i, b: __m256i) -> __m256i { _mm256_xor_si256(a, b) }
+        #[target_feature(enable = "avx2")] #[inline] pub fn not(a: __m256i) -> __m256i { _mm256_xor_si256(a, _mm256_set1_epi32(-1)) }
+        // a & !b
+        #[target_feature(enable = "avx2")] #[inline] pub fn andnot(a: __m256i, b: __m256i) -> __m256i { _mm256_andnot_si256(b, a) }
+        #[target_feature(enable = "avx2")] #[inline] pub fn shl(a: __m256i, n: i32) -> __m256i { _mm256_sll_epi32(a, _mm_cvtsi32_si128(n)) }
+        #[target_feature(enable = "avx2")] #[inline] pub fn shr(a: __m256i, n: i32) -> __m256i { _mm256_srl_epi32(a, _mm_cvtsi32_si128(n)) }
+        #[target_feature(enable = "avx2")] #[inline] pub fn sllv(a: __m256i, n: __m256i) -> __m256i { _mm256_sllv_epi32(a, n) }
+        #[target_feature(enable = "avx2")] #[inline] pub fn sub(a: __m256i, b: __m256i) -> __m256i { _mm256_sub_epi32(a, b) }
+        #[target_feature(enable = "avx2")] #[inline] pub fn eq(a: __m256i, b: __m256i) -> __m256i { _mm256_cmpeq_epi32(a, b) }
+        // Signed comparison, every value compared is small.
+        #[target_feature(enable = "avx2")] #[inline] pub fn gt(a: __m256i, b: __m256i) -> __m256i { _mm256_cmpgt_epi32(a, b) }
+        #[target_feature(enable = "avx2")] #[inline] pub fn select(mask: __m256i, a: __m256i, b: __m256i) -> __m256i { _mm256_blendv_epi8(b, a, mask) }
+
+        // SWAR popcount in each 32 bit lane.
+        #[target_feature(enable = "avx2")]
+        #[inline]
+        pub fn popcount(a: __m256i) -> __m256i {
+            let mut x = _mm256_sub_epi32(a, _mm256_and_si256(_mm256_srli_epi32(a, 1), _mm256_set1_epi32(0x5555_5555)));
+            x = _mm256_add_epi32(
+                _mm256_and_si256(x, _mm256_set1_epi32(0x3333_3333)),
+                _mm256_and_si256(_mm256_srli_epi32(x, 2), _mm256_set1_epi32(0x3333_3333)),
+            );
+            x = _mm256_and_si256(_mm256_add_epi32(x, _mm256_srli_epi32(x, 4)), _mm256_set1_epi32(0x0F0F_0F0F));
+            x = _mm256_add_epi32(x, _mm256_srli_epi32(x, 8));
+            x = _mm256_add_epi32(x, _mm256_srli_epi32(x, 16));
+            _mm256_and_si256(x, _mm256_set1_epi32(0x3F))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{deck::Deck, evaluate::rank_hand_bits};
+
+    // Every hand on a set of random boards, through both the scalar and AVX2 paths.
+    #[test]
+    fn test_rank_hands_bits_batch() {
+        let mut rng = fastrand::Rng::with_seed(7);
+        let mut deck = Deck::new();
+
+        for _ in 0..200 {
+            deck.shuffle_with(&mut rng);
+            let (board, rest) = deck.as_ref().split_at(5);
+            let hands = rest.iter().enumerate()
+                .flat_map(|(i, &a)| rest[i + 1..].iter().map(move |&b| Hand(a, b)))
+                .collect::<Vec<Hand>>();
+            let expected = hands.iter()
+                .map(|hand| rank_hand_bits(&[hand.0, hand.1, board[0], board[1], board[2], board[3], board[4]]).unwrap())
+                .collect::<Vec<HandRank>>();
+
+            let mut ranks = vec![HandRank::HighCard(0); hands.len()];
+            rank_hands_scalar(&BoardBits::new(board), &hands, &mut ranks);
+            assert_eq!(ranks, expected, "scalar, board {:?}", board);
+
+            ranks.fill(HandRank::HighCard(0));
+            rank_hands_bits_batch(board, &hands, &mut ranks);
+            assert_eq!(ranks, expected, "batch, board {:?}", board);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::sync::OnceLock;
+use crate::{card::{Card, Rank, Suit}, error::{Error, Result}};
+use super::{senzee::{eval_5_senzee, eval_6_senzee, eval_7_senzee}, HandRank};
+
+// Compact evaluator, a few hundred KB of tables built in memory on first use instead of the
+// 2+2 table on disk.  Ranks are the same as the 2+2 and senzee evaluators.
+//
+// Each rank has a key, chosen so the sum of keys is unique for every multiset of up to 7
+// ranks with at most 4 of each.  The key sums are perfect hashed into a table of non-flush
+// ranks, and a flush is looked up by the rank bits of the flush suit.
+
+pub fn rank_hand_compact(hand: &[Card]) -> Result<HandRank> {
+    match hand.len() {
+        5..=7 => Ok(HandRank::from(eval_compact(hand))),
+        _ => Err(Error::InvalidHandSize(hand.len())),
+    }
+}
+
+// Raw evaluation of 5 to 7 cards.
+#[inline]
+pub fn eval_compact(hand: &[Card]) -> u16 {
+    debug_assert!(hand.len() >= 5 && hand.len() <= 7);
+    hand.iter().fold(CompactHand::default(), |h, &card| h.add_card(card)).eval(tables())
+}
+
+// Greedy smallest keys with unique sums over all multisets of at most 7 ranks.
+const RANK_KEYS: [u32; 13] = [
+    1, 5, 24, 112, 521, 2247, 9244, 30823, 103066, 250154, 667453, 1526359, 3453520,
+];
+
+// Hash rows are 2^ROW_BITS key sums wide, each row is placed in the table at its own offset
+// where it doesn't overlap the rows placed before it.
+const ROW_BITS: u32 = 10;
+const ROW_MASK: u32 = (1 << ROW_BITS) - 1;
+const MAX_KEY: u32 = 7 * RANK_KEYS[12];
+
+// Suit counts are packed in nibbles above the key sum, adding 3 to a nibble sets its top bit
+// once the suit has 5 cards.
+const SUIT_SHIFT: u32 = 32;
+const FLUSH_CHECK: u64 = 0x8888;
+
+struct Tables {
+    offsets: Vec<u32>,
+    ranks:   Vec<u16>,
+    flushes: Vec<u16>,
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(Tables::build)
+}
+
+// Key sum and suit counts, with the rank bits of each suit in 16 bit lanes.
+#[derive(Debug, Clone, Copy, Default)]
+struct CompactHand {
+    key:   u64,
+    suits: u64,
+}
+
+impl CompactHand {
+    #[inline(always)]
+    fn add_card(self, card: Card) -> Self {
+        let (rank, suit) = (card.rank_u8() as u64, card.suit_u8() as u64);
+        Self {
+            key:   self.key + (RANK_KEYS[rank as usize] as u64 | 1 << (SUIT_SHIFT as u64 + 4 * suit)),
+            suits: self.suits | 1 << (16 * suit + rank),
+        }
+    }
+
+    #[inline(always)]
+    fn eval(self, tables: &Tables) -> u16 {
+        let flush = ((self.key >> SUIT_SHIFT) + 0x3333) & FLUSH_CHECK;
+        if flush != 0 {
+            let suit = flush.trailing_zeros() / 4;
+            tables.flushes[(self.suits >> (16 * suit)) as usize & 0x1FFF]
+        } else {
+            let key = self.key as u32;
+            tables.ranks[(tables.offsets[(key >> ROW_BITS) as usize] + (key & ROW_MASK)) as usize]
+        }
+    }
+}
+
+impl Tables {
+    fn build() -> Self {
+        let mut rows = vec![Vec::new(); (MAX_KEY >> ROW_BITS) as usize + 1];
+        let mut counts = [0_u8; 13];
+        rank_multisets(&mut counts, 0, 0, &mut |counts, size| {
+            if size >= 5 {
+                let key = counts.iter().zip(RANK_KEYS).map(|(&c, k)| c as u32 * k).sum::<u32>();
+                rows[(key >> ROW_BITS) as usize].push((key & ROW_MASK, eval_multiset(counts, size)));
+            }
+        });
+
+        // First fit of the fullest rows first.
+        let mut order = (0..rows.len()).collect::<Vec<usize>>();
+        order.sort_by_key(|&row| std::cmp::Reverse(rows[row].len()));
+        let mut offsets = vec![0; rows.len()];
+        let mut used = Vec::new();
+        let mut ranks = Vec::new();
+        for row in order.into_iter().filter(|&row| !rows[row].is_empty()) {
+            let entries = &rows[row];
+            let offset = (0..).find(|&offset| {
+                entries.iter().all(|&(col, _)| !used.get((offset + col) as usize).copied().unwrap_or(false))
+            }).unwrap();
+            for &(col, rank) in entries {
+                let slot = (offset + col) as usize;
+                if slot >= used.len() {
+                    used.resize(slot + 1, false);
+                    ranks.resize(slot + 1, 0);
+                }
+                used[slot] = true;
+                ranks[slot] = rank;
+            }
+            offsets[row] = offset;
+        }
+
+        let mut flushes = vec![0; 1 << 13];
+        for (rank_bits, flush) in flushes.iter_mut().enumerate() {
+            if (5..=7).contains(&(rank_bits as u32).count_ones()) {
+                let cards = (0..13)
+                    .filter(|rank| rank_bits & 1 << rank != 0)
+                    .map(|rank| Card::new(Rank::from(rank as u8), Suit::from(0)).bit_mask())
+                    .collect::<Vec<u32>>();
+                *flush = eval_senzee(&cards);
+            }
+        }
+
+        Self { offsets, ranks, flushes }
+    }
+}
+
+// Calls f with the count of each rank for every multiset of at most 7 ranks.
+fn rank_multisets(counts: &mut [u8; 13], rank: usize, size: u8, f: &mut impl FnMut(&[u8; 13], u8)) {
+    if rank == 13 {
+        f(counts, size);
+        return;
+    }
+    for count in 0..=4.min(7 - size) {
+        counts[rank] = count;
+        rank_multisets(counts, rank + 1, size + count, f);
+    }
+    counts[rank] = 0;
+}
+
+// Best non-flush rank of a multiset, dealing the cards round the suits so no suit has 5.
+fn eval_multiset(counts: &[u8; 13], size: u8) -> u16 {
+    let mut cards = Vec::with_capacity(size as usize);
+    for (rank, &count) in counts.iter().enumerate() {
+        for _ in 0..count {
+            let suit = Suit::from((cards.len() % 4) as u8);
+            cards.push(Card::new(Rank::from(rank as u8), suit).bit_mask());
+        }
+    }
+    eval_senzee(&cards)
+}
+
+fn eval_senzee(cards: &[u32]) -> u16 {
+    match cards.len() {
+        5 => eval_5_senzee(cards),
+        6 => eval_6_senzee(cards),
+        7 => eval_7_senzee(cards),
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{deck::Deck, evaluate::{load_lookup_table, BoardState}};
+
+    fn lookup() -> Vec<i32> {
+        let lookup_path = std::env::var("LOOKUP_PATH").unwrap_or("data/lookup_table.bin".to_string());
+        load_lookup_table(std::path::Path::new(&lookup_path)).unwrap()
+    }
+
+    fn assert_matches_2p2(cards: &[Card], lookup: &[i32]) {
+        let hand = cards.iter().fold(CompactHand::default(), |hand, &card| hand.add_card(card));
+        let state = BoardState::new(cards, lookup);
+        assert_eq!(hand.eval(tables()), state.rank(lookup), "{:?}", cards);
+    }
+
+    // Random 7 card hands and hands at the edges of the categories against the 2+2 table.
+    #[test]
+    fn test_compact_matches_2p2_sampled() {
+        let lookup = lookup();
+        for hand in [
+            "As Ks Qs Js Ts 9s 8s", "5h 4h 3h 2h Ah Kd Kc", "Ac 2d 3h 4s 5c Kd Qh", "Ac Kd Qh Js Tc 2d 3h",
+            "As Ah Ad Ac Kd Kc Ks", "Kd Kc Ks Qh Qd Qs 2c", "2c 2d 2h 3c 3d 7s 8s", "Ah Kh 9h 5h 3h 2h 4d",
+            "7c 5d 4h 3s 2c 9d Jh",
+        ] {
+            assert_matches_2p2(&Card::vec_from_str(hand).unwrap(), &lookup);
+        }
+
+        let mut deck = Deck::new();
+        let mut rng = fastrand::Rng::with_seed(7);
+        let mut cards = [Card::default(); 7];
+        for _ in 0..20_000 {
+            deck.deal_random(&mut rng, 0, &mut cards);
+            assert_matches_2p2(&cards, &lookup);
+        }
+    }
+
+    // Every 7 card hand against the 2+2 table, both built up a card at a time.  Takes minutes,
+    // run with --ignored.
+    #[test]
+    #[ignore]
+    fn test_compact_matches_2p2() {
+        let lookup = lookup();
+        let tables = tables();
+        let cards = (0..52).map(Card).collect::<Vec<Card>>();
+
+        let mut count = 0_u64;
+        let mut stack = vec![(CompactHand::default(), BoardState::default(), 0)];
+        while let Some((hand, state, next)) = stack.pop() {
+            if state.num_cards() == 7 {
+                assert_eq!(hand.eval(tables), state.rank(&lookup), "{:?}", state);
+                count += 1;
+                continue;
+            }
+            for &card in &cards[next..=52 + state.num_cards() - 7] {
+                stack.push((hand.add_card(card), state.add_card(card, &lookup), card.0 as usize + 1));
+            }
+        }
+        assert_eq!(count, 133_784_560);
+    }
+
+    #[test]
+    fn test_rank_hand_compact() {
+        let hand = Card::vec_from_str("As Ks Qs Js Ts 2d 3c").unwrap();
+        assert!(matches!(rank_hand_compact(&hand).unwrap(), HandRank::StraightFlush(_)));
+        assert!(matches!(rank_hand_compact(&hand[2..]).unwrap(), HandRank::HighCard(_)));
+        let hand = Card::vec_from_str("As Ks Qs Js 9s 9d 9c").unwrap();
+        assert!(matches!(rank_hand_compact(&hand).unwrap(), HandRank::Flush(_)));
+        assert!(matches!(rank_hand_compact(&hand[..4]), Err(Error::InvalidHandSize(4))));
+
+        let tables = tables();
+        let size = tables.offsets.len() * 4 + tables.ranks.len() * 2 + tables.flushes.len() * 2;
+        assert!(size < 512 * 1024, "tables are {} bytes", size);
+    }
+}
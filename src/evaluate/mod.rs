@@ -1,10 +1,14 @@
+mod batch;
 mod bits;
+mod compact;
 mod two_plus_two;
 mod senzee;
 #[cfg(test)]
 mod tests;
 
+pub use batch::{eval_7_2p2_batch, rank_hands_bits_batch};
 pub use bits::rank_hand_bits;
+pub use compact::{rank_hand_compact, eval_compact};
 pub use senzee::{
     rank_hand_senzee, 
     rank_bit_mask_senzee,
@@ -18,6 +22,7 @@ pub use two_plus_two::{
     eval_6_2p2,
     eval_7_2p2,
     load_lookup_table,
+    BoardState,
 };
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
@@ -3,7 +3,8 @@ use super::{
     HandRank,
     bits::rank_hand_bits,
     senzee::rank_hand_senzee,
-    two_plus_two::{rank_hand_2p2, load_lookup_table},
+    two_plus_two::{rank_hand_2p2, eval_7_2p2, load_lookup_table, BoardState},
+    batch::eval_7_2p2_batch,
 };
 
 fn cards_arr(hand_str: &str) -> [Card; 5] {
@@ -203,4 +204,46 @@ mod tests {
         tester.test_hand_comparison("As Ad Kh Qs Js", "As Ad Kh Qs Ts");
         tester.test_hand_comparison("As Ks Qs Js 9s", "As Ks Qs Js 8s");
     }
+
+    #[test]
+    fn test_eval_7_2p2_batch() {
+        let tester = EvaluatorTester::new();
+        let mut rng = fastrand::Rng::with_seed(7);
+        let mut deck = crate::deck::Deck::new();
+
+        for _ in 0..200 {
+            deck.shuffle_with(&mut rng);
+            let (board, rest) = deck.as_ref().split_at(5);
+            let hands = rest.iter().enumerate()
+                .flat_map(|(i, &a)| rest[i + 1..].iter().map(move |&b| crate::hand::Hand(a, b)))
+                .collect::<Vec<_>>();
+
+            let mut ranks = vec![0; hands.len()];
+            eval_7_2p2_batch(&BoardState::new(board, &tester.lookup_table), &hands, &tester.lookup_table, &mut ranks);
+            for (hand, &rank) in hands.iter().zip(ranks.iter()) {
+                let cards = [hand.0, hand.1, board[0], board[1], board[2], board[3], board[4]];
+                assert_eq!(rank, eval_7_2p2(&cards, &tester.lookup_table), "{:?} on {:?}", hand, board);
+            }
+        }
+    }
+
+    #[test]
+    fn test_board_state() {
+        let tester = EvaluatorTester::new();
+        let lookup = &tester.lookup_table;
+        let board = cards_arr("Ks Qs 7d 7h 2s");
+        let hand = crate::hand::Hand(Card::from_str("As").unwrap(), Card::from_str("Js").unwrap());
+
+        // Built up from the flop in any order, the state matches a full walk.
+        let flop = BoardState::new(&board[..3], lookup);
+        let river = flop.add_card(board[3], lookup).add_card(board[4], lookup);
+        let reversed = board.iter().rev().fold(BoardState::default(), |state, &card| state.add_card(card, lookup));
+        assert_eq!(river.num_cards(), 5);
+        assert_eq!(river.mask(), board.iter().fold(0, |mask, card| mask | card.mask()));
+
+        let cards = [hand.0, hand.1, board[0], board[1], board[2], board[3], board[4]];
+        assert_eq!(river.eval_hand(&hand, lookup), eval_7_2p2(&cards, lookup));
+        assert_eq!(reversed.eval_hand(&hand, lookup), eval_7_2p2(&cards, lookup));
+        assert_eq!(HandRank::from(river.eval_hand(&hand, lookup)).get_type(), HandRankType::Flush);
+    }
 }
@@ -1,5 +1,5 @@
 use std::{io::{self, Read}, path::Path};
-use crate::{card::Card, error::{Error, Result}};
+use crate::{card::Card, hand::Hand, error::{Error, Result}};
 use super::HandRank;
 
 const TABLE_SIZE: usize = 32_487_834;
@@ -76,4 +76,63 @@ pub fn eval_7_2p2(hand: &[Card], lookup_table: &[i32]) -> u16 {
     r = lookup_table[r + hand[5].0 as usize + 1] as usize;
     r = lookup_table[r + hand[6].0 as usize + 1] as usize;
     r as u16
-}
\ No newline at end of file
+}
+
+// Position in the table walk after some cards.  The walk gives the same result in any card
+// order, so a state for the board cards is shared by every hand on that board, and states
+// for the flop and turn can be kept across the runouts that extend them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoardState {
+    position:  usize,
+    num_cards: usize,
+    mask:      u64,
+}
+
+impl Default for BoardState {
+    fn default() -> Self {
+        Self { position: 53, num_cards: 0, mask: 0 }
+    }
+}
+
+impl BoardState {
+    pub fn new(board: &[Card], lookup_table: &[i32]) -> Self {
+        board.iter().fold(Self::default(), |state, &card| state.add_card(card, lookup_table))
+    }
+
+    #[inline(always)]
+    pub fn add_card(&self, card: Card, lookup_table: &[i32]) -> Self {
+        debug_assert!(self.num_cards < 7);
+        Self {
+            position:  lookup_table[self.position + card.0 as usize + 1] as usize,
+            num_cards: self.num_cards + 1,
+            mask:      self.mask | card.mask(),
+        }
+    }
+
+    pub fn num_cards(&self) -> usize {
+        self.num_cards
+    }
+
+    // Mask of the cards added so far.
+    pub fn mask(&self) -> u64 {
+        self.mask
+    }
+
+    // Rank of the cards added so far, 5 to 7 cards.
+    pub fn rank(&self, lookup_table: &[i32]) -> u16 {
+        debug_assert!(self.num_cards >= 5);
+        if self.num_cards == 7 {
+            self.position as u16
+        } else {
+            lookup_table[self.position] as u16
+        }
+    }
+
+    // Rank of a hand on a full 5 card board, two lookups.
+    #[inline(always)]
+    pub fn eval_hand(&self, hand: &Hand, lookup_table: &[i32]) -> u16 {
+        debug_assert!(self.num_cards == 5);
+        let r = lookup_table[self.position + hand.0.0 as usize + 1] as usize;
+        lookup_table[r + hand.1.0 as usize + 1] as u16
+    }
+}
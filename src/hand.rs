@@ -1,5 +1,6 @@
 use thiserror::Error;
 use regex::Regex;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use crate::card::{Card, Rank, CardParseError};
 
 #[derive(Error, Debug)]
@@ -33,6 +34,23 @@ impl std::hash::Hash for Hand {
     }
 }
 
+// Hands serialize as both cards in one string, eg. "AhKd".
+impl Serialize for Hand {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{}{}", self.0.ascii(), self.1.ascii()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Hand {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        match Card::vec_from_str(&s).map_err(de::Error::custom)?[..] {
+            [a, b] if a != b => Ok(Hand(a, b)),
+            _ => Err(de::Error::custom(format!("expected a hand of two cards, got {}", s))),
+        }
+    }
+}
+
 impl Hand {
     pub fn from_str(s: &str) -> Result<Hand, HandParseError> {
         
@@ -237,4 +255,14 @@ mod tests {
             }
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_serde() {
+        let hand = Hand::from_str("Ah Kd").unwrap();
+        let json = serde_json::to_string(&hand).unwrap();
+        assert_eq!(json, "\"AhKd\"");
+        assert_eq!(serde_json::from_str::<Hand>(&json).unwrap(), hand);
+        assert!(serde_json::from_str::<Hand>("\"AhKdQs\"").is_err());
+        assert!(serde_json::from_str::<Hand>("\"AhAh\"").is_err());
+    }
+}
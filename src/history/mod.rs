@@ -0,0 +1,146 @@
+use crate::{board::Board, hand::Hand, solver::position_name};
+
+mod ohh;
+mod parser;
+mod replay;
+#[cfg(test)]
+mod tests;
+
+pub use ohh::{read_ohh, write_ohh};
+pub use parser::*;
+pub use replay::{replay_hand, HandResult, PlayerSession, SessionReport};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Street {
+    Preflop,
+    Flop,
+    Turn,
+    River,
+}
+
+// Amounts are as written in the history, the chips put in by the action.  A raise puts in the
+// difference between its total and what the player already had in on the street.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ActionKind {
+    Ante(f64),
+    SmallBlind(f64),
+    BigBlind(f64),
+    // Dead small and big blinds posted together out of position.
+    Blinds(f64),
+    Fold,
+    Check,
+    Call(f64),
+    Bet(f64),
+    Raise { by: f64, to: f64 },
+    // A bet or raise no one called, given back to the player.
+    Uncalled(f64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryAction {
+    pub street: Street,
+    // Index into the hand's players.
+    pub player: usize,
+    pub kind:   ActionKind,
+    pub all_in: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Player {
+    pub seat:       u8,
+    pub name:       String,
+    pub stack:      f64,
+    pub position:   &'static str,
+    // Known when dealt to the hero or shown at showdown.
+    pub hole_cards: Option<Hand>,
+    // Won from the pot, after rake.
+    pub collected:  f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct HandHistory {
+    pub site:        String,
+    pub id:          String,
+    pub tournament:  Option<String>,
+    pub game:        String,
+    pub small_blind: f64,
+    pub big_blind:   f64,
+    pub date:        String,
+    pub table:       String,
+    pub max_seats:   u8,
+    pub button:      u8,
+    // Players dealt in, in seat order.
+    pub players:     Vec<Player>,
+    pub hero:        Option<usize>,
+    pub actions:     Vec<HistoryAction>,
+    pub board:       Board,
+    pub pot:         f64,
+    pub rake:        f64,
+}
+
+impl HandHistory {
+    pub fn player(&self, name: &str) -> Option<usize> {
+        self.players.iter().position(|player| player.name == name)
+    }
+
+    pub fn street_actions(&self, street: Street) -> impl Iterator<Item = &HistoryAction> {
+        self.actions.iter().filter(move |action| action.street == street)
+    }
+
+    // Chips each player put in the pot, less any uncalled bet returned to them.
+    pub fn invested(&self) -> Vec<f64> {
+        let mut invested = vec![0.0; self.players.len()];
+        let mut street = vec![0.0; self.players.len()];
+        let mut current = Street::Preflop;
+        for action in &self.actions {
+            if action.street != current {
+                current = action.street;
+                street.iter_mut().for_each(|chips| *chips = 0.0);
+            }
+            let chips = match action.kind {
+                // Antes and dead small blinds don't count towards calling the big blind.
+                ActionKind::Ante(amount) => {
+                    invested[action.player] += amount;
+                    continue;
+                },
+                ActionKind::Blinds(amount) => {
+                    invested[action.player] += amount;
+                    street[action.player] += self.big_blind;
+                    continue;
+                },
+                ActionKind::Raise { to, .. } => to - street[action.player],
+                ActionKind::Uncalled(amount) => -amount,
+                ActionKind::SmallBlind(amount) | ActionKind::BigBlind(amount) |
+                ActionKind::Call(amount) | ActionKind::Bet(amount) => amount,
+                ActionKind::Fold | ActionKind::Check => 0.0,
+            };
+            invested[action.player] += chips;
+            street[action.player] += chips;
+        }
+        invested
+    }
+
+    // What each player won or lost on the hand.
+    pub fn net(&self) -> Vec<f64> {
+        self.players.iter().zip(self.invested()).map(|(player, invested)| player.collected - invested).collect()
+    }
+}
+
+// Positions from the seat after the button round to the button, named in order of action with
+// the blinds last.  Heads-up the button is the small blind.
+pub(crate) fn positions(seats: &[u8], button: u8) -> Vec<&'static str> {
+    let num_players = seats.len();
+    let mut order = (0..num_players).collect::<Vec<usize>>();
+    order.sort_by_key(|&i| (seats[i] <= button, seats[i]));
+
+    let mut positions = vec![""; num_players];
+    for (i, &player) in order.iter().enumerate() {
+        let action = match num_players {
+            1 => 0,
+            2 => 1 - i,
+            _ => (i + num_players - 2) % num_players,
+        };
+        positions[player] = position_name(action, num_players);
+    }
+    positions
+}
@@ -0,0 +1,354 @@
+use std::{collections::HashMap, io::Write};
+use serde::{Deserialize, Serialize};
+use crate::{board::Board, card::Card, hand::Hand};
+use super::{parser::HistoryParseError, positions, ActionKind, HandHistory, HistoryAction, Player, Street};
+
+const SPEC_VERSION: &str = "1.4.6";
+
+// Open Hand History, https://hh-specs.handhistory.org.  Only the fields a hand history holds are
+// written, fields missing on reading take their defaults.
+#[derive(Debug, Serialize, Deserialize)]
+struct OhhFile {
+    ohh: Ohh,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Ohh {
+    spec_version:       String,
+    site_name:          String,
+    #[serde(default)]
+    network_name:       String,
+    #[serde(default)]
+    internal_version:   String,
+    #[serde(default)]
+    tournament:         bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tournament_info:    Option<TournamentInfo>,
+    game_number:        String,
+    // Written as the history gave it, PokerStars dates are in the site's time zone.
+    #[serde(default)]
+    start_date_utc:     String,
+    #[serde(default)]
+    table_name:         String,
+    game_type:          String,
+    bet_limit:          BetLimit,
+    table_size:         u8,
+    dealer_seat:        u8,
+    small_blind_amount: f64,
+    big_blind_amount:   f64,
+    #[serde(default)]
+    ante_amount:        f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hero_player_id:     Option<usize>,
+    players:            Vec<OhhPlayer>,
+    rounds:             Vec<OhhRound>,
+    #[serde(default)]
+    pots:               Vec<OhhPot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TournamentInfo {
+    tournament_number: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BetLimit {
+    bet_type: String,
+    #[serde(default)]
+    bet_cap:  f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OhhPlayer {
+    id:             usize,
+    seat:           u8,
+    name:           String,
+    starting_stack: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OhhRound {
+    id:      usize,
+    street:  String,
+    #[serde(default)]
+    cards:   Vec<Card>,
+    actions: Vec<OhhAction>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OhhAction {
+    action_number: usize,
+    player_id:     usize,
+    action:        String,
+    #[serde(default)]
+    amount:        f64,
+    #[serde(default)]
+    is_allin:      bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    cards:         Vec<Card>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OhhPot {
+    number:      usize,
+    amount:      f64,
+    #[serde(default)]
+    rake:        f64,
+    player_wins: Vec<OhhWin>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OhhWin {
+    player_id:  usize,
+    win_amount: f64,
+}
+
+const STREETS: [(Street, &str); 4] = [
+    (Street::Preflop, "Preflop"),
+    (Street::Flop, "Flop"),
+    (Street::Turn, "Turn"),
+    (Street::River, "River"),
+];
+
+const LIMITS: [(&str, &str); 3] = [("No Limit", "NL"), ("Pot Limit", "PL"), ("Limit", "FL")];
+
+impl HandHistory {
+    // The hand as an OHH object, players identified by their index.
+    pub fn to_ohh(&self) -> String {
+        serde_json::to_string_pretty(&OhhFile { ohh: self.ohh() }).expect("hand histories serialize")
+    }
+
+    pub fn from_ohh(json: &str) -> Result<HandHistory, HistoryParseError> {
+        serde_json::from_str::<OhhFile>(json)?.ohh.hand()
+    }
+
+    fn ohh(&self) -> Ohh {
+        let folded = |player: usize| self.actions.iter().any(|action| action.player == player && action.kind == ActionKind::Fold);
+        let showdown = (0..self.players.len()).filter(|&player| !folded(player)).count() > 1;
+        let cards = self.board.as_vec();
+        let hero_cards = self.hero.and_then(|hero| self.players[hero].hole_cards.map(|hand| (hero, hand)));
+        let mut number = 0;
+        let mut next = || {
+            number += 1;
+            number
+        };
+
+        let mut rounds = Vec::new();
+        for (id, &(street, name)) in STREETS.iter().enumerate() {
+            let dealt = match street {
+                Street::Preflop => 0..0,
+                Street::Flop => 0..3,
+                Street::Turn => 3..4,
+                Street::River => 4..5,
+            };
+            if dealt.end > cards.len() {
+                break;
+            }
+
+            let mut actions = Vec::new();
+            if let (Street::Preflop, Some((hero, hand))) = (street, hero_cards) {
+                actions.push(OhhAction { action_number: next(), player_id: hero, action: "Dealt Cards".to_string(), amount: 0.0, is_allin: false, cards: vec![hand.0, hand.1] });
+            }
+            for action in self.street_actions(street) {
+                let (name, amount) = match action.kind {
+                    ActionKind::Ante(amount) => ("Post Ante", amount),
+                    ActionKind::SmallBlind(amount) => ("Post SB", amount),
+                    ActionKind::BigBlind(amount) => ("Post BB", amount),
+                    ActionKind::Blinds(amount) => ("Post Dead", amount),
+                    ActionKind::Fold => ("Fold", 0.0),
+                    ActionKind::Check => ("Check", 0.0),
+                    ActionKind::Call(amount) => ("Call", amount),
+                    ActionKind::Bet(amount) => ("Bet", amount),
+                    ActionKind::Raise { to, .. } => ("Raise", to),
+                    // OHH leaves uncalled bets to the pots.
+                    ActionKind::Uncalled(_) => continue,
+                };
+                actions.push(OhhAction { action_number: next(), player_id: action.player, action: name.to_string(), amount, is_allin: action.all_in, cards: Vec::new() });
+            }
+            rounds.push(OhhRound { id, street: name.to_string(), cards: cards[dealt].to_vec(), actions });
+        }
+
+        let mut shown = Vec::new();
+        for (id, player) in self.players.iter().enumerate() {
+            let Some(hand) = player.hole_cards else { continue };
+            if Some(id) == self.hero && (folded(id) || !showdown) {
+                continue;
+            }
+            let action = if folded(id) { "Mucks Cards" } else { "Shows Cards" };
+            shown.push(OhhAction { action_number: next(), player_id: id, action: action.to_string(), amount: 0.0, is_allin: false, cards: vec![hand.0, hand.1] });
+        }
+        if !shown.is_empty() {
+            rounds.push(OhhRound { id: rounds.len(), street: "Showdown".to_string(), cards: Vec::new(), actions: shown });
+        }
+
+        let ante = self.actions.iter().find_map(|action| match action.kind {
+            ActionKind::Ante(amount) => Some(amount),
+            _ => None,
+        });
+        let limit = self.game.strip_prefix("Hold'em ").unwrap_or(&self.game);
+        Ohh {
+            spec_version:       SPEC_VERSION.to_string(),
+            site_name:          self.site.clone(),
+            network_name:       self.site.clone(),
+            internal_version:   env!("CARGO_PKG_VERSION").to_string(),
+            tournament:         self.tournament.is_some(),
+            tournament_info:    self.tournament.clone().map(|tournament_number| TournamentInfo { tournament_number }),
+            game_number:        self.id.clone(),
+            start_date_utc:     self.date.clone(),
+            table_name:         self.table.clone(),
+            game_type:          "Holdem".to_string(),
+            bet_limit:          BetLimit {
+                bet_type: LIMITS.iter().find(|(name, _)| *name == limit).map_or("NL", |(_, bet_type)| bet_type).to_string(),
+                bet_cap:  0.0,
+            },
+            table_size:         self.max_seats,
+            dealer_seat:        self.button,
+            small_blind_amount: self.small_blind,
+            big_blind_amount:   self.big_blind,
+            ante_amount:        ante.unwrap_or(0.0),
+            hero_player_id:     self.hero,
+            players:            self.players.iter().enumerate().map(|(id, player)| OhhPlayer {
+                id,
+                seat:           player.seat,
+                name:           player.name.clone(),
+                starting_stack: player.stack,
+            }).collect(),
+            rounds,
+            pots:               vec![OhhPot {
+                number:      0,
+                amount:      self.pot,
+                rake:        self.rake,
+                player_wins: self.players.iter().enumerate()
+                    .filter(|(_, player)| player.collected > 0.0)
+                    .map(|(player_id, player)| OhhWin { player_id, win_amount: player.collected })
+                    .collect(),
+            }],
+        }
+    }
+}
+
+impl Ohh {
+    fn hand(self) -> Result<HandHistory, HistoryParseError> {
+        let invalid = |message: String| HistoryParseError::InvalidOhh(self.game_number.clone(), message);
+        if self.game_type != "Holdem" {
+            return Err(invalid(format!("unsupported game type {}", self.game_type)));
+        }
+        let limit = LIMITS.iter().find(|(_, bet_type)| *bet_type == self.bet_limit.bet_type)
+            .ok_or_else(|| invalid(format!("unknown bet type {}", self.bet_limit.bet_type)))?;
+
+        let mut players = self.players.iter().collect::<Vec<&OhhPlayer>>();
+        players.sort_by_key(|player| player.seat);
+        if !(2..=10).contains(&players.len()) {
+            return Err(HistoryParseError::InvalidPlayerCount(self.game_number.clone(), players.len()));
+        }
+        let index = players.iter().enumerate().map(|(i, player)| (player.id, i)).collect::<HashMap<usize, usize>>();
+        let seats = players.iter().map(|player| player.seat).collect::<Vec<u8>>();
+        let players = players.iter().zip(positions(&seats, self.dealer_seat)).map(|(player, position)| Player {
+            seat:       player.seat,
+            name:       player.name.clone(),
+            stack:      player.starting_stack,
+            position,
+            hole_cards: None,
+            collected:  0.0,
+        }).collect();
+
+        let mut hand = HandHistory {
+            site:        self.site_name.clone(),
+            id:          self.game_number.clone(),
+            tournament:  self.tournament_info.as_ref().map(|info| info.tournament_number.clone()),
+            game:        format!("Hold'em {}", limit.0),
+            small_blind: self.small_blind_amount,
+            big_blind:   self.big_blind_amount,
+            date:        self.start_date_utc.clone(),
+            table:       self.table_name.clone(),
+            max_seats:   self.table_size,
+            button:      self.dealer_seat,
+            players,
+            hero:        self.hero_player_id.and_then(|id| index.get(&id).copied()),
+            actions:     Vec::new(),
+            board:       Board::default(),
+            pot:         self.pots.iter().map(|pot| pot.amount).sum(),
+            rake:        self.pots.iter().map(|pot| pot.rake).sum(),
+        };
+
+        let mut board = Vec::new();
+        let mut street = Street::Preflop;
+        for round in &self.rounds {
+            match STREETS.iter().find(|(_, name)| *name == round.street) {
+                Some(&(next, _)) => street = next,
+                None if round.street == "Showdown" => {},
+                None => return Err(invalid(format!("unknown street {}", round.street))),
+            }
+            board.extend(&round.cards);
+
+            // The highest bet on the street, what a raise raises by is measured from.
+            let mut bet = 0.0_f64;
+            for action in &round.actions {
+                let player = *index.get(&action.player_id).ok_or_else(|| invalid(format!("unknown player id {}", action.player_id)))?;
+                let kind = match action.action.as_str() {
+                    "Dealt Cards" | "Shows Cards" | "Mucks Cards" => {
+                        if let [a, b] = action.cards[..] {
+                            hand.players[player].hole_cards = Some(Hand(a, b));
+                        }
+                        continue;
+                    },
+                    "Post Ante" => ActionKind::Ante(action.amount),
+                    "Post SB" => ActionKind::SmallBlind(action.amount),
+                    "Post BB" => ActionKind::BigBlind(action.amount),
+                    "Post Dead" => ActionKind::Blinds(action.amount),
+                    "Fold" => ActionKind::Fold,
+                    "Check" => ActionKind::Check,
+                    "Call" => ActionKind::Call(action.amount),
+                    "Bet" => ActionKind::Bet(action.amount),
+                    "Raise" => ActionKind::Raise { by: cents(action.amount - bet), to: action.amount },
+                    other => return Err(invalid(format!("unsupported action {}", other))),
+                };
+                match kind {
+                    ActionKind::BigBlind(amount) | ActionKind::Bet(amount) | ActionKind::Raise { to: amount, .. } => bet = bet.max(amount),
+                    ActionKind::Blinds(_) => bet = bet.max(hand.big_blind),
+                    _ => {},
+                }
+                hand.actions.push(HistoryAction { street, player, kind, all_in: action.is_allin });
+            }
+        }
+        // Observed hands name a hero without dealing them cards.
+        hand.hero = hand.hero.filter(|&hero| hand.players[hero].hole_cards.is_some());
+        hand.board = Board::from_vec(board).map_err(|error| invalid(error.to_string()))?;
+
+        for win in self.pots.iter().flat_map(|pot| &pot.player_wins) {
+            let player = *index.get(&win.player_id).ok_or_else(|| invalid(format!("unknown player id {}", win.player_id)))?;
+            hand.players[player].collected += win.win_amount;
+        }
+
+        // Chips put in beyond the pots were an uncalled bet, returned to whoever put in the most.
+        let invested = hand.invested();
+        let uncalled = cents(invested.iter().sum::<f64>() - hand.pot);
+        if uncalled > 0.0 && !self.pots.is_empty() {
+            let player = (0..invested.len()).max_by(|&a, &b| invested[a].total_cmp(&invested[b])).unwrap();
+            let street = hand.actions.iter().rev().find(|action| action.player == player).map_or(Street::Preflop, |action| action.street);
+            hand.actions.push(HistoryAction { street, player, kind: ActionKind::Uncalled(uncalled), all_in: false });
+        }
+        Ok(hand)
+    }
+}
+
+// Differences of amounts, rounded to the cent histories write them in.
+fn cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+// Reads OHH objects one after another, as OHH files hold them.
+pub fn read_ohh(text: &str) -> Result<Vec<HandHistory>, HistoryParseError> {
+    serde_json::Deserializer::from_str(text)
+        .into_iter::<OhhFile>()
+        .map(|file| file?.ohh.hand())
+        .collect()
+}
+
+// Writes each hand as an OHH object followed by a blank line.
+pub fn write_ohh<W: Write>(hands: &[HandHistory], writer: &mut W) -> std::io::Result<()> {
+    for hand in hands {
+        writeln!(writer, "{}\n", hand.to_ohh())?;
+    }
+    Ok(())
+}
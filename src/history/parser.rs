@@ -0,0 +1,350 @@
+use std::str::FromStr;
+use regex::Regex;
+use thiserror::Error;
+use crate::{board::Board, card::Card, hand::Hand};
+use super::{positions, ActionKind, HandHistory, HistoryAction, Player, Street};
+
+// Line numbers count from 1 over the whole text parsed.
+#[derive(Error, Debug)]
+pub enum HistoryParseError {
+    #[error("Line {0}: expected a PokerStars hand header, got: {1}")]
+    InvalidHeader(usize, String),
+
+    #[error("Line {0}: unsupported game {1}, only Hold'em is supported")]
+    UnsupportedGame(usize, String),
+
+    #[error("Line {0}: could not parse: {1}")]
+    InvalidLine(usize, String),
+
+    #[error("Line {0}: invalid amount: {1}")]
+    InvalidAmount(usize, String),
+
+    #[error("Line {0}: invalid cards: {1}")]
+    InvalidCards(usize, String),
+
+    #[error("Line {0}: unknown player: {1}")]
+    UnknownPlayer(usize, String),
+
+    #[error("Hand {0} has no table line")]
+    MissingTable(String),
+
+    #[error("Hand {0} has {1} players dealt in, expected 2 to 10")]
+    InvalidPlayerCount(String, usize),
+
+    #[error("Invalid Open Hand History JSON: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+
+    #[error("Hand {0}: {1}")]
+    InvalidOhh(String, String),
+}
+
+type Result<T> = std::result::Result<T, HistoryParseError>;
+
+// Parses every hand in a file of PokerStars hand histories.
+pub fn parse_histories(text: &str) -> Result<Vec<HandHistory>> {
+    let lines = text.lines().collect::<Vec<&str>>();
+    let starts = lines.iter()
+        .enumerate()
+        .filter(|(_, line)| is_header(line))
+        .map(|(i, _)| i)
+        .collect::<Vec<usize>>();
+    if starts.is_empty() {
+        let (line, text) = lines.iter().enumerate().find(|(_, line)| !line.trim().is_empty()).unwrap_or((0, &""));
+        return Err(HistoryParseError::InvalidHeader(line + 1, text.trim().to_string()));
+    }
+
+    let parser = Parser::new();
+    starts.iter().enumerate().map(|(i, &start)| {
+        let end = starts.get(i + 1).copied().unwrap_or(lines.len());
+        parser.parse(&lines[start..end], start)
+    }).collect()
+}
+
+impl FromStr for HandHistory {
+    type Err = HistoryParseError;
+
+    fn from_str(text: &str) -> Result<HandHistory> {
+        let lines = text.lines().collect::<Vec<&str>>();
+        let start = lines.iter().position(|line| !line.trim().is_empty()).unwrap_or(0);
+        Parser::new().parse(&lines[start..], start)
+    }
+}
+
+fn is_header(line: &str) -> bool {
+    let line = line.trim_start_matches('\u{feff}');
+    line.starts_with("PokerStars ") && line.contains(" #")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Section {
+    Seats,
+    Street(Street),
+    Showdown,
+    Summary,
+}
+
+// Compiled once for all the hands of a file.
+struct Parser {
+    header: Regex,
+    blinds: Regex,
+    table:  Regex,
+    seat:   Regex,
+    cards:  Regex,
+}
+
+impl Parser {
+    fn new() -> Self {
+        Self {
+            header: Regex::new(r"^PokerStars (?:Zoom |Home Game )?(?:Hand|Game) #(\d+):\s+(?:Tournament #(\d+), [^ ]+ [A-Z]* ?)?(.+?) (?:- Level \S+ )?\(").unwrap(),
+            blinds: Regex::new(r"\(([^()/]+)/([^()/ ]+)(?: [A-Z]{3})?\)").unwrap(),
+            table:  Regex::new(r"^Table '(.+)' (\d+)-max (?:\(Play Money\) )?Seat #(\d+) is the button").unwrap(),
+            seat:   Regex::new(r"^Seat (\d+): (.+) \(([^ ()]+) in chips(?:, [^()]*)?\)(.*)$").unwrap(),
+            cards:  Regex::new(r"\[([^\]]*)\]").unwrap(),
+        }
+    }
+
+    // The offset is the line number of the first line, less one.
+    fn parse(&self, lines: &[&str], offset: usize) -> Result<HandHistory> {
+        let first = lines[0].trim().trim_start_matches('\u{feff}');
+        let header = self.header.captures(first)
+            .ok_or_else(|| HistoryParseError::InvalidHeader(offset + 1, first.to_string()))?;
+        let game = header[3].trim().to_string();
+        if !game.starts_with("Hold'em") {
+            return Err(HistoryParseError::UnsupportedGame(offset + 1, game));
+        }
+        // The blinds are the last pair in brackets, after the tournament level if there is one.
+        let blinds = self.blinds.captures_iter(first).last()
+            .ok_or_else(|| HistoryParseError::InvalidHeader(offset + 1, first.to_string()))?;
+
+        let mut hand = HandHistory {
+            site:        "PokerStars".to_string(),
+            id:          header[1].to_string(),
+            tournament:  header.get(2).map(|id| id.as_str().to_string()),
+            game,
+            small_blind: amount(&blinds[1], offset + 1)?,
+            big_blind:   amount(&blinds[2], offset + 1)?,
+            date:        first.rsplit(" - ").next().unwrap_or("").trim().to_string(),
+            table:       String::new(),
+            max_seats:   0,
+            button:      0,
+            players:     Vec::new(),
+            hero:        None,
+            actions:     Vec::new(),
+            board:       Board::default(),
+            pot:         0.0,
+            rake:        0.0,
+        };
+
+        let mut board = Vec::new();
+        let mut section = Section::Seats;
+        // Showdown lines belong to the last street dealt.
+        let mut street = Street::Preflop;
+        let mut has_table = false;
+        for (i, line) in lines.iter().enumerate().skip(1) {
+            let number = offset + i + 1;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(marker) = line.strip_prefix("*** ") {
+                section = match marker.split(" ***").next().unwrap_or("") {
+                    "HOLE CARDS" => Section::Street(Street::Preflop),
+                    "FLOP" => Section::Street(Street::Flop),
+                    "TURN" => Section::Street(Street::Turn),
+                    "RIVER" => Section::Street(Street::River),
+                    "SHOW DOWN" => Section::Showdown,
+                    "SUMMARY" => Section::Summary,
+                    _ => return Err(HistoryParseError::InvalidLine(number, line.to_string())),
+                };
+                if let Section::Street(next) = section {
+                    street = next;
+                }
+                // Each street line shows the new cards in the last brackets.
+                if let Some(cards) = self.cards.captures_iter(line).last() {
+                    board.extend(parse_cards(&cards[1], number)?);
+                }
+                continue;
+            }
+
+            if section == Section::Seats {
+                if let Some(table) = self.table.captures(line) {
+                    hand.table = table[1].to_string();
+                    hand.max_seats = table[2].parse().map_err(|_| HistoryParseError::InvalidLine(number, line.to_string()))?;
+                    hand.button = table[3].parse().map_err(|_| HistoryParseError::InvalidLine(number, line.to_string()))?;
+                    has_table = true;
+                    continue;
+                }
+                if let Some(seat) = self.seat.captures(line) {
+                    if !seat[4].contains("sitting out") {
+                        hand.players.push(Player {
+                            seat:       seat[1].parse().map_err(|_| HistoryParseError::InvalidLine(number, line.to_string()))?,
+                            name:       seat[2].to_string(),
+                            stack:      amount(&seat[3], number)?,
+                            position:   "",
+                            hole_cards: None,
+                            collected:  0.0,
+                        });
+                    }
+                    continue;
+                }
+            }
+
+            if section == Section::Summary {
+                self.summary(&mut hand, line, number)?;
+                continue;
+            }
+
+            self.line(&mut hand, street, line, number)?;
+        }
+
+        if !has_table {
+            return Err(HistoryParseError::MissingTable(hand.id));
+        }
+        if !(2..=10).contains(&hand.players.len()) {
+            return Err(HistoryParseError::InvalidPlayerCount(hand.id, hand.players.len()));
+        }
+        let seats = hand.players.iter().map(|player| player.seat).collect::<Vec<u8>>();
+        for (player, position) in hand.players.iter_mut().zip(positions(&seats, hand.button)) {
+            player.position = position;
+        }
+        hand.board = Board::from_vec(board)
+            .map_err(|error| HistoryParseError::InvalidCards(offset + lines.len(), error.to_string()))?;
+        Ok(hand)
+    }
+
+    // A line during the deal, an action or one of the dealer's.  Lines the replay doesn't need,
+    // like chat or players joining, are skipped.
+    fn line(&self, hand: &mut HandHistory, street: Street, line: &str, number: usize) -> Result<()> {
+        if let Some(rest) = line.strip_prefix("Dealt to ") {
+            let player = player_prefix(hand, rest).ok_or_else(|| HistoryParseError::UnknownPlayer(number, rest.to_string()))?;
+            let cards = &rest[hand.players[player].name.len()..];
+            // Zoom tables deal the others face down, with no cards.
+            if let Some(cards) = self.cards.captures_iter(cards).last().filter(|cards| !cards[1].trim().is_empty()) {
+                hand.players[player].hole_cards = Some(parse_hand(&cards[1], number)?);
+                hand.hero = Some(player);
+            }
+            return Ok(());
+        }
+        if let Some(rest) = line.strip_prefix("Uncalled bet (") {
+            let (chips, name) = rest.split_once(") returned to ")
+                .ok_or_else(|| HistoryParseError::InvalidLine(number, line.to_string()))?;
+            let player = hand.player(name).ok_or_else(|| HistoryParseError::UnknownPlayer(number, name.to_string()))?;
+            let kind = ActionKind::Uncalled(amount(chips, number)?);
+            hand.actions.push(HistoryAction { street, player, kind, all_in: false });
+            return Ok(());
+        }
+
+        let Some(player) = player_prefix(hand, line) else {
+            return Ok(());
+        };
+        let rest = &line[hand.players[player].name.len()..];
+        if let Some(won) = rest.strip_prefix(" collected ") {
+            let chips = won.split(" from ").next().unwrap_or(won);
+            hand.players[player].collected += amount(chips, number)?;
+            return Ok(());
+        }
+        let Some(action) = rest.strip_prefix(": ") else {
+            return Ok(());
+        };
+
+        if let Some(shown) = action.strip_prefix("shows ") {
+            let cards = self.cards.captures(shown).ok_or_else(|| HistoryParseError::InvalidLine(number, line.to_string()))?;
+            hand.players[player].hole_cards = Some(parse_hand(&cards[1], number)?);
+            return Ok(());
+        }
+
+        let (action, all_in) = match action.strip_suffix(" and is all-in") {
+            Some(action) => (action, true),
+            None => (action, false),
+        };
+        let (verb, chips) = action.split_once(' ').unwrap_or((action, ""));
+        let kind = match verb {
+            "folds" => ActionKind::Fold,
+            "checks" => ActionKind::Check,
+            "calls" => ActionKind::Call(amount(chips, number)?),
+            "bets" => ActionKind::Bet(amount(chips, number)?),
+            "raises" => {
+                let (by, to) = chips.split_once(" to ").ok_or_else(|| HistoryParseError::InvalidLine(number, line.to_string()))?;
+                ActionKind::Raise { by: amount(by, number)?, to: amount(to, number)? }
+            },
+            "posts" => {
+                if let Some(chips) = chips.strip_prefix("small blind ") {
+                    ActionKind::SmallBlind(amount(chips, number)?)
+                } else if let Some(chips) = chips.strip_prefix("big blind ") {
+                    ActionKind::BigBlind(amount(chips, number)?)
+                } else if let Some(chips) = chips.strip_prefix("the ante ") {
+                    ActionKind::Ante(amount(chips, number)?)
+                } else if let Some(chips) = chips.strip_prefix("small & big blinds ") {
+                    ActionKind::Blinds(amount(chips, number)?)
+                } else {
+                    return Err(HistoryParseError::InvalidLine(number, line.to_string()));
+                }
+            },
+            // Statuses and mucks, nothing to record.
+            "mucks" | "doesn't" | "is" | "has" | "sits" | "sitting" | "cashed" | "leaves" | "joins" | "will" | "removed" => return Ok(()),
+            _ => return Err(HistoryParseError::InvalidLine(number, line.to_string())),
+        };
+        hand.actions.push(HistoryAction { street, player, kind, all_in });
+        Ok(())
+    }
+
+    fn summary(&self, hand: &mut HandHistory, line: &str, number: usize) -> Result<()> {
+        if let Some(pot) = line.strip_prefix("Total pot ") {
+            let mut parts = pot.split(" | ");
+            let total = parts.next().unwrap_or("").split_whitespace().next().unwrap_or("");
+            hand.pot = amount(total, number)?;
+            for part in parts {
+                if let Some(rake) = part.strip_prefix("Rake ") {
+                    hand.rake = amount(rake, number)?;
+                }
+            }
+            return Ok(());
+        }
+        // Mucked hands only show up in the summary.
+        if let Some(seat) = line.strip_prefix("Seat ") {
+            let (seat, rest) = seat.split_once(": ").ok_or_else(|| HistoryParseError::InvalidLine(number, line.to_string()))?;
+            let player = hand.players.iter().position(|player| player.seat.to_string() == seat);
+            if let (Some(player), true) = (player, rest.contains(" showed [") || rest.contains(" mucked [")) {
+                if let Some(cards) = self.cards.captures(rest) {
+                    hand.players[player].hole_cards = Some(parse_hand(&cards[1], number)?);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// The player whose name starts the line, the longest name if one is the prefix of another.
+fn player_prefix(hand: &HandHistory, line: &str) -> Option<usize> {
+    hand.players.iter()
+        .enumerate()
+        .filter(|(_, player)| {
+            line.strip_prefix(player.name.as_str())
+                .is_some_and(|rest| rest.starts_with(':') || rest.starts_with(' ') || rest.starts_with('['))
+        })
+        .max_by_key(|(_, player)| player.name.len())
+        .map(|(i, _)| i)
+}
+
+// Amounts like "$1,250.50", "€2" or "1500".
+fn amount(text: &str, number: usize) -> Result<f64> {
+    text.trim()
+        .trim_start_matches(['$', '€', '£'])
+        .replace(',', "")
+        .parse()
+        .map_err(|_| HistoryParseError::InvalidAmount(number, text.to_string()))
+}
+
+fn parse_cards(text: &str, number: usize) -> Result<Vec<Card>> {
+    text.split_whitespace()
+        .map(|card| Card::from_str(card).map_err(|_| HistoryParseError::InvalidCards(number, text.to_string())))
+        .collect()
+}
+
+fn parse_hand(text: &str, number: usize) -> Result<Hand> {
+    match parse_cards(text, number)?[..] {
+        [a, b] => Ok(Hand(a, b)),
+        _ => Err(HistoryParseError::InvalidCards(number, text.to_string())),
+    }
+}
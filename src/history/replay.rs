@@ -0,0 +1,181 @@
+use prettytable::{Table, Row, Cell};
+use crate::{equity::{equity_enumerate, EquityParams}, prelude::*};
+use super::{ActionKind, HandHistory, Street};
+
+// What each player of a hand won, and would have won on average had every all-in been run out
+// by its equity.
+#[derive(Debug, Clone)]
+pub struct HandResult {
+    pub id:        String,
+    pub big_blind: f64,
+    pub net:       Vec<f64>,
+    pub expected:  Vec<f64>,
+    // Players who got it in before the river with their cards known.
+    pub all_in:    Vec<bool>,
+}
+
+// Replays a hand, running equity_enumerate on the board as it was when the betting ended if
+// everyone still in is all-in before the river and has shown their cards.  Each side pot goes to
+// its players by their equity among themselves, less its share of the rake.
+pub fn replay_hand(hand: &HandHistory, lookup: &[i32]) -> Result<HandResult> {
+    let net = hand.net();
+    let mut result = HandResult {
+        id:        hand.id.clone(),
+        big_blind: hand.big_blind,
+        expected:  net.clone(),
+        all_in:    vec![false; net.len()],
+        net,
+    };
+
+    let folded = |player: usize| hand.actions.iter().any(|action| action.player == player && action.kind == ActionKind::Fold);
+    let live = (0..hand.players.len()).filter(|&player| !folded(player)).collect::<Vec<usize>>();
+    let last_street = hand.actions.iter()
+        .filter(|action| !matches!(action.kind, ActionKind::Uncalled(_)))
+        .map(|action| action.street)
+        .max()
+        .unwrap_or(Street::Preflop);
+    let dealt = match last_street {
+        Street::Preflop => 0,
+        Street::Flop => 3,
+        Street::Turn => 4,
+        Street::River => 5,
+    };
+    let cards = hand.board.as_vec();
+    if live.len() < 2 || cards.len() <= dealt || live.iter().any(|&player| hand.players[player].hole_cards.is_none()) {
+        return Ok(result);
+    }
+    let board = Board::from_vec(cards[..dealt].to_vec()).expect("prefix of a valid board");
+
+    // Side pots from the smallest all-in up, each shared by the live players who covered it.
+    let invested = hand.invested();
+    let mut levels = live.iter().map(|&player| invested[player]).collect::<Vec<f64>>();
+    levels.sort_by(f64::total_cmp);
+    levels.dedup();
+
+    let mut pots = Vec::new();
+    let mut previous = 0.0;
+    for level in levels {
+        let chips = invested.iter().map(|&chips| chips.min(level) - chips.min(previous)).sum::<f64>();
+        let players = live.iter().copied().filter(|&player| invested[player] >= level).collect::<Vec<usize>>();
+        pots.push((chips, players));
+        previous = level;
+    }
+    let collected = hand.players.iter().map(|player| player.collected).sum::<f64>();
+    let total = pots.iter().map(|(chips, _)| chips).sum::<f64>();
+    let after_rake = if total > 0.0 { collected / total } else { 0.0 };
+
+    for &player in &live {
+        result.expected[player] = -invested[player];
+    }
+    for (chips, players) in pots {
+        let chips = chips * after_rake;
+        if players.len() == 1 {
+            result.expected[players[0]] += chips;
+            continue;
+        }
+
+        let ranges = players.iter().map(|&player| {
+            let mut range = Range::default();
+            range.set_hand(&hand.players[player].hole_cards.unwrap(), true);
+            range
+        }).collect();
+        let equities = equity_enumerate(EquityParams {
+            ranges,
+            board: board.clone(),
+            lookup,
+            reporter: None,
+            seed:     None,
+            cancel:   None,
+        })?;
+        for (i, &player) in players.iter().enumerate() {
+            result.expected[player] += chips * (equities.wins[i] + equities.ties[i]) / equities.total;
+        }
+    }
+    live.iter().for_each(|&player| result.all_in[player] = true);
+    Ok(result)
+}
+
+// Totals for one player over a session, in big blinds.
+#[derive(Debug, Clone)]
+pub struct PlayerSession {
+    pub name:     String,
+    pub hands:    u32,
+    pub all_ins:  u32,
+    pub won:      f64,
+    pub expected: f64,
+}
+
+impl PlayerSession {
+    pub fn bb_per_100(&self) -> f64 {
+        self.won / self.hands as f64 * 100.0
+    }
+
+    // All-in adjusted winnings, "EV bb/100".
+    pub fn ev_bb_per_100(&self) -> f64 {
+        self.expected / self.hands as f64 * 100.0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionReport {
+    pub hands:   Vec<HandResult>,
+    // In order of hands played, most first.
+    pub players: Vec<PlayerSession>,
+}
+
+impl SessionReport {
+    pub fn replay(hands: &[HandHistory], lookup: &[i32]) -> Result<SessionReport> {
+        let mut players: Vec<PlayerSession> = Vec::new();
+        let mut results = Vec::with_capacity(hands.len());
+        for hand in hands {
+            let result = replay_hand(hand, lookup)?;
+            for (i, player) in hand.players.iter().enumerate() {
+                let session = match players.iter().position(|session| session.name == player.name) {
+                    Some(session) => &mut players[session],
+                    None => {
+                        players.push(PlayerSession { name: player.name.clone(), hands: 0, all_ins: 0, won: 0.0, expected: 0.0 });
+                        players.last_mut().unwrap()
+                    },
+                };
+                session.hands += 1;
+                session.won += result.net[i] / result.big_blind;
+                session.expected += result.expected[i] / result.big_blind;
+                session.all_ins += result.all_in[i] as u32;
+            }
+            results.push(result);
+        }
+        players.sort_by_key(|session| std::cmp::Reverse(session.hands));
+        Ok(SessionReport { hands: results, players })
+    }
+
+    pub fn player(&self, name: &str) -> Option<&PlayerSession> {
+        self.players.iter().find(|session| session.name == name)
+    }
+
+    pub fn print(&self) {
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![
+            Cell::new("Player"),
+            Cell::new("Hands"),
+            Cell::new("All-ins"),
+            Cell::new("Won (bb)"),
+            Cell::new("bb/100"),
+            Cell::new("EV (bb)"),
+            Cell::new("EV bb/100"),
+            Cell::new("Luck (bb)"),
+        ]));
+        for session in &self.players {
+            table.add_row(Row::new(vec![
+                Cell::new(&session.name),
+                Cell::new(&session.hands.to_string()),
+                Cell::new(&session.all_ins.to_string()),
+                Cell::new(&format!("{:.2}", session.won)),
+                Cell::new(&format!("{:.2}", session.bb_per_100())),
+                Cell::new(&format!("{:.2}", session.expected)),
+                Cell::new(&format!("{:.2}", session.ev_bb_per_100())),
+                Cell::new(&format!("{:+.2}", session.won - session.expected)),
+            ]));
+        }
+        table.printstd();
+    }
+}
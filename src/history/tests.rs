@@ -0,0 +1,173 @@
+use std::str::FromStr;
+use crate::{card::Card, hand::Hand};
+use crate::evaluate::load_lookup_table;
+use super::{parse_histories, read_ohh, replay_hand, write_ohh, ActionKind, HandHistory, HistoryParseError, SessionReport, Street};
+
+const CASH: &str = include_str!("fixtures/cash.txt");
+const TOURNAMENT: &str = include_str!("fixtures/tournament.txt");
+const OHH: &str = include_str!("fixtures/ohh.json");
+
+fn hand(s: &str) -> Option<Hand> {
+    Some(Hand::from_str(s).unwrap())
+}
+
+fn assert_close(a: &[f64], b: &[f64]) {
+    assert!(a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-9), "{:?} vs {:?}", a, b);
+}
+
+#[test]
+fn test_parse_cash() {
+    let hands = parse_histories(CASH).unwrap();
+    assert_eq!(hands.len(), 2);
+
+    let hand0 = &hands[0];
+    assert_eq!(hand0.id, "235483829813");
+    assert_eq!(hand0.tournament, None);
+    assert_eq!(hand0.game, "Hold'em No Limit");
+    assert_eq!((hand0.small_blind, hand0.big_blind), (0.05, 0.10));
+    assert_eq!(hand0.date, "2022/04/10 12:34:56 ET");
+    assert_eq!((hand0.table.as_str(), hand0.max_seats, hand0.button), ("Aludra II", 6, 3));
+
+    // The player sitting out isn't dealt in.
+    let names = hand0.players.iter().map(|player| player.name.as_str()).collect::<Vec<&str>>();
+    assert_eq!(names, ["player one", "Villain", "Hero", "fish", "reg"]);
+    let positions = hand0.players.iter().map(|player| player.position).collect::<Vec<&str>>();
+    assert_eq!(positions, ["HJ", "CO", "BTN", "SB", "BB"]);
+    assert_eq!(hand0.players[1].stack, 12.35);
+
+    assert_eq!(hand0.hero, Some(2));
+    assert_eq!(hand0.players[2].hole_cards, hand("Ah Kd"));
+    assert_eq!(hand0.players[1].hole_cards, hand("Jc Jd"));
+    assert_eq!(hand0.players[0].hole_cards, None);
+    assert_eq!(hand0.board.as_vec(), Card::vec_from_str("2c 7d Js Th 3s").unwrap());
+
+    assert_eq!(hand0.street_actions(Street::Preflop).count(), 7);
+    let flop = hand0.street_actions(Street::Flop).collect::<Vec<_>>();
+    assert_eq!(flop[2].kind, ActionKind::Raise { by: 1.40, to: 2.0 });
+    let turn = hand0.street_actions(Street::Turn).collect::<Vec<_>>();
+    assert!(turn[1].all_in && turn[1].kind == ActionKind::Bet(7.70));
+    assert_eq!(hand0.street_actions(Street::River).count(), 0);
+
+    assert_eq!((hand0.pot, hand0.rake), (20.35, 0.85));
+    assert_close(&hand0.invested(), &[0.0, 10.0, 10.0, 0.05, 0.30]);
+    assert_close(&hand0.net(), &[0.0, 9.50, -10.0, -0.05, -0.30]);
+
+    // Hands are split on their headers, the uncalled raise goes back to the button.
+    let hand1 = &hands[1];
+    assert_eq!(hand1.players[1].stack, 1000.0);
+    assert_eq!(hand1.players[2].position, "BTN");
+    assert_eq!(hand1.board.as_vec().len(), 0);
+    assert_eq!(hand1.players[3].hole_cards, hand("8c 8d"));
+    assert_close(&hand1.net(), &[-0.10, 0.0, 0.15, -0.05]);
+}
+
+#[test]
+fn test_parse_tournament() {
+    let hand = HandHistory::from_str(TOURNAMENT).unwrap();
+    assert_eq!(hand.tournament.as_deref(), Some("3300000001"));
+    assert_eq!(hand.game, "Hold'em No Limit");
+    assert_eq!((hand.small_blind, hand.big_blind), (25.0, 50.0));
+    assert_eq!(hand.players.iter().map(|player| player.position).collect::<Vec<&str>>(), ["BTN", "SB", "BB"]);
+    assert_eq!(hand.players[2].stack, 800.0);
+
+    let preflop = hand.street_actions(Street::Preflop).collect::<Vec<_>>();
+    assert_eq!(preflop[0].kind, ActionKind::Ante(5.0));
+    assert!(preflop[5].all_in);
+    assert_eq!(preflop.last().unwrap().kind, ActionKind::Uncalled(650.0));
+
+    assert_close(&hand.invested(), &[800.0, 30.0, 800.0]);
+    assert_close(&hand.net(), &[830.0, -30.0, -800.0]);
+    assert_eq!(hand.net().iter().sum::<f64>(), -hand.rake);
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(matches!(parse_histories("Full Tilt Poker Game #1"), Err(HistoryParseError::InvalidHeader(1, _))));
+
+    let omaha = TOURNAMENT.replace("Hold'em No Limit", "Omaha Pot Limit");
+    assert!(matches!(HandHistory::from_str(&omaha), Err(HistoryParseError::UnsupportedGame(1, _))));
+
+    let amount = TOURNAMENT.replace("calls 745", "calls 7x5");
+    assert!(matches!(HandHistory::from_str(&amount), Err(HistoryParseError::InvalidAmount(15, _))));
+
+    let cards = TOURNAMENT.replace("[Kd]", "[Kx]");
+    assert!(matches!(HandHistory::from_str(&cards), Err(HistoryParseError::InvalidCards(18, _))));
+
+    let player = TOURNAMENT.replace("returned to alpha", "returned to gamma");
+    assert!(matches!(HandHistory::from_str(&player), Err(HistoryParseError::UnknownPlayer(16, _))));
+
+    let action = TOURNAMENT.replace("beta: folds", "beta: limps");
+    assert!(matches!(HandHistory::from_str(&action), Err(HistoryParseError::InvalidLine(14, _))));
+
+    // Line numbers count over the whole file.
+    let second = CASH.replace("fish: raises $0.20", "fish: raises $0.2O");
+    assert!(matches!(parse_histories(&second), Err(HistoryParseError::InvalidAmount(57, _))));
+}
+
+#[test]
+fn test_replay() {
+    let lookup = load_lookup_table("./data/lookup_table.bin").unwrap();
+    let hands = parse_histories(CASH).unwrap();
+
+    // All-in on the turn, AK has the four queens of the 44 rivers against the set, after rake.
+    let result = replay_hand(&hands[0], &lookup).unwrap();
+    assert_eq!(result.all_in, [false, true, true, false, false]);
+    assert_close(&result.expected, &[0.0, 40.0 / 44.0 * 19.50 - 10.0, 4.0 / 44.0 * 19.50 - 10.0, -0.05, -0.30]);
+    let result = replay_hand(&hands[1], &lookup).unwrap();
+    assert_eq!(result.expected, result.net);
+
+    let report = SessionReport::replay(&hands, &lookup).unwrap();
+    let hero = report.player("Hero").unwrap();
+    assert_eq!((hero.hands, hero.all_ins), (2, 1));
+    assert!((hero.bb_per_100() - -5000.0).abs() < 1e-6);
+    assert!((hero.ev_bb_per_100() - (4.0 / 44.0 * 195.0 - 100.0) * 50.0).abs() < 1e-6);
+    assert_eq!(report.players[0].hands, 2);
+
+    // Preflop the queens are about a 70% favourite.
+    let tournament = HandHistory::from_str(TOURNAMENT).unwrap();
+    let result = replay_hand(&tournament, &lookup).unwrap();
+    let equity = (result.expected[2] + 800.0) / 1630.0;
+    assert!((0.68..0.74).contains(&equity), "{}", equity);
+    assert!((result.expected.iter().sum::<f64>() - result.net.iter().sum::<f64>()).abs() < 1e-6);
+}
+
+#[test]
+fn test_ohh() {
+    let imported = HandHistory::from_ohh(OHH).unwrap();
+    assert_eq!((imported.site.as_str(), imported.id.as_str(), imported.game.as_str()), ("Example Poker", "90817", "Hold'em No Limit"));
+    assert_eq!(imported.players.iter().map(|player| player.position).collect::<Vec<&str>>(), ["BB", "SB"]);
+    assert_eq!(imported.hero, Some(0));
+    assert_eq!(imported.players[0].hole_cards, hand("Kc Qc"));
+    assert_eq!(imported.board.as_vec(), Card::vec_from_str("Ah 7c 2d").unwrap());
+    assert_eq!(imported.street_actions(Street::Preflop).nth(2).unwrap().kind, ActionKind::Raise { by: 4.0, to: 6.0 });
+
+    // The bet left out of the pot is returned.
+    assert_eq!(imported.actions.last().unwrap().kind, ActionKind::Uncalled(8.0));
+    assert_close(&imported.net(), &[-6.0, 6.0]);
+
+    let mut hands = parse_histories(CASH).unwrap();
+    hands.push(HandHistory::from_str(TOURNAMENT).unwrap());
+    hands.push(imported);
+    let mut json = Vec::new();
+    write_ohh(&hands, &mut json).unwrap();
+    let read = read_ohh(std::str::from_utf8(&json).unwrap()).unwrap();
+    assert_eq!(read.len(), hands.len());
+    for (a, b) in hands.iter().zip(&read) {
+        assert_eq!((&a.id, &a.tournament, &a.game, a.button), (&b.id, &b.tournament, &b.game, b.button));
+        assert_eq!(a.players, b.players);
+        assert_eq!(a.hero, b.hero);
+        assert_eq!(a.actions, b.actions);
+        assert_eq!(a.board.as_vec(), b.board.as_vec());
+        assert_close(&a.net(), &b.net());
+    }
+
+    // Without dealt cards the hero's hand is unknown, and the hand writes out without them.
+    let observed = OHH.replace("\"Dealt Cards\", \"cards\": [\"Kc\", \"Qc\"]", "\"Dealt Cards\"");
+    let imported = HandHistory::from_ohh(&observed).unwrap();
+    assert_eq!((imported.hero, imported.players[0].hole_cards), (None, None));
+    assert_eq!(HandHistory::from_ohh(&imported.to_ohh()).unwrap().actions, imported.actions);
+
+    let stud = OHH.replace("\"Holdem\"", "\"StudHi\"");
+    assert!(matches!(HandHistory::from_ohh(&stud), Err(HistoryParseError::InvalidOhh(_, _))));
+    assert!(matches!(read_ohh("{\"ohh\": {}}"), Err(HistoryParseError::InvalidJson(_))));
+}
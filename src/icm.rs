@@ -0,0 +1,222 @@
+use crate::{equity::{equity_enumerate, EquityParams, EquityResults}, error::{Error, Result}, prelude::*};
+
+// Going over every order of finish, the work doubles with each player.
+pub const MAX_ICM_PLAYERS: usize = 20;
+
+// Malmuth-Harville tournament equity, each player taking the next place with probability
+// proportional to their stack among those left.  Players with no chips share whatever places are
+// left once only they remain.  At most MAX_ICM_PLAYERS players.
+pub fn icm_equities(stacks: &[f64], payouts: &[f64]) -> Vec<f64> {
+    let num_players = stacks.len();
+    assert!(num_players <= MAX_ICM_PLAYERS, "too many players for ICM");
+
+    let total = stacks.iter().sum::<f64>();
+    let places = payouts.len().min(num_players);
+    let mut equities = vec![0.0; num_players];
+
+    // Probability of the players in each mask taking the first places, in any order.
+    let mut finished = vec![0.0; 1 << num_players];
+    finished[0] = 1.0;
+    for mask in 0..finished.len() {
+        let probability = finished[mask];
+        let place = (mask as u32).count_ones() as usize;
+        if probability == 0.0 || place >= places {
+            continue;
+        }
+
+        let left = total - (0..num_players).filter(|i| mask >> i & 1 == 1).map(|i| stacks[i]).sum::<f64>();
+        let remaining = (0..num_players).filter(|i| mask >> i & 1 == 0).collect::<Vec<usize>>();
+        if left <= 0.0 {
+            let share = payouts[place..].iter().take(remaining.len()).sum::<f64>() / remaining.len() as f64;
+            remaining.iter().for_each(|&i| equities[i] += probability * share);
+            continue;
+        }
+
+        for i in remaining {
+            let next = probability * stacks[i] / left;
+            equities[i] += next * payouts[place];
+            finished[mask | 1 << i] += next;
+        }
+    }
+    equities
+}
+
+// A player facing an all-in.  Stacks are the chips behind, after what each put in this hand.
+#[derive(Debug, Clone)]
+pub struct IcmSpot {
+    pub stacks:   Vec<f64>,
+    pub invested: Vec<f64>,
+    pub payouts:  Vec<f64>,
+    pub hero:     usize,
+    // Bet the most, anything they have behind stays out of the pot.
+    pub villain:  usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct IcmDecision {
+    // Hero's share of the pot at showdown.
+    pub equity:          f64,
+    // Equity calling breaks even at in tournament equity and in chips.
+    pub required_equity: f64,
+    pub chip_required:   f64,
+    // Hero's tournament equity after each decision.
+    pub call_ev:         f64,
+    pub fold_ev:         f64,
+}
+
+impl IcmDecision {
+    pub fn should_call(&self) -> bool {
+        self.call_ev > self.fold_ev
+    }
+}
+
+impl IcmSpot {
+    fn check(&self) -> Result<()> {
+        let num_players = self.stacks.len();
+        if num_players > MAX_ICM_PLAYERS {
+            return Err(Error::InvalidIcmSpot("ICM is limited to 20 players"));
+        }
+        if self.invested.len() != num_players {
+            return Err(Error::InvalidIcmSpot("a stack and an investment are needed for each player"));
+        }
+        if self.hero >= num_players || self.villain >= num_players || self.hero == self.villain {
+            return Err(Error::InvalidIcmSpot("hero and villain must be different players"));
+        }
+        if self.invested[self.villain] <= self.invested[self.hero] {
+            return Err(Error::InvalidIcmSpot("villain must have bet more than hero"));
+        }
+        Ok(())
+    }
+
+    // Stacks after hero folds, or calls and wins, ties or loses.
+    fn outcomes(&self) -> [Vec<f64>; 4] {
+        let (hero, villain) = (self.hero, self.villain);
+        let mut folded = self.stacks.clone();
+        folded[villain] += self.invested.iter().sum::<f64>();
+
+        // Hero calls as much as they can, what they can't cover goes back to the villain.
+        let call = f64::min(self.stacks[hero], self.invested[villain] - self.invested[hero]);
+        let matched = self.invested[hero] + call;
+        let excess = self.invested[villain] - matched;
+        let pot = self.invested.iter().sum::<f64>() + call - excess;
+
+        let mut called = self.stacks.clone();
+        called[hero] -= call;
+        called[villain] += excess;
+        let mut won = called.clone();
+        won[hero] += pot;
+        let mut tied = called.clone();
+        tied[hero] += pot / 2.0;
+        tied[villain] += pot / 2.0;
+        let mut lost = called;
+        lost[villain] += pot;
+
+        [folded, won, tied, lost]
+    }
+
+    // Hero's tournament equity calling and folding, given the all-in results of hero's range
+    // against the villain's.
+    pub fn decide(&self, results: &EquityResults) -> Result<IcmDecision> {
+        self.check()?;
+
+        let win = results.wins[0] / results.total;
+        // Each player of a two way tie gets half a tie.
+        let tie = 2.0 * results.ties[0] / results.total;
+        let lose = 1.0 - win - tie;
+
+        let [folded, won, tied, lost] = self.outcomes();
+        let ev = |stacks: &[f64]| icm_equities(stacks, &self.payouts)[self.hero];
+        let (fold_ev, won_ev, tied_ev, lost_ev) = (ev(&folded), ev(&won), ev(&tied), ev(&lost));
+
+        let chips = |stacks: &[f64]| stacks[self.hero];
+        Ok(IcmDecision {
+            equity:          win + tie / 2.0,
+            required_equity: (fold_ev - lost_ev) / (won_ev - lost_ev),
+            chip_required:   (chips(&folded) - chips(&lost)) / (chips(&won) - chips(&lost)),
+            call_ev:         win * won_ev + tie * tied_ev + lose * lost_ev,
+            fold_ev,
+        })
+    }
+}
+
+// Enumerates hero's range against the villain's on the board and decides the spot.
+pub fn icm_call(spot: &IcmSpot, ranges: [Range; 2], board: Board, lookup: &[i32]) -> Result<IcmDecision> {
+    spot.check()?;
+    let results = equity_enumerate(EquityParams {
+        ranges:   ranges.to_vec(),
+        board,
+        lookup,
+        reporter: None,
+        seed:     None,
+        cancel:   None,
+    })?;
+    spot.decide(&results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert!(a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-9), "{:?} vs {:?}", a, b);
+    }
+
+    #[test]
+    fn test_icm_equities() {
+        let equities = icm_equities(&[5000.0, 3000.0, 2000.0], &[50.0, 30.0, 20.0]);
+        assert_close(&equities, &[38.392857142857, 32.75, 28.857142857143]);
+
+        let equities = icm_equities(&[4000.0, 3000.0, 2000.0, 1000.0], &[50.0, 30.0, 20.0]);
+        assert_close(&equities, &[33.603174603175, 29.488095238095, 23.587301587302, 13.321428571429]);
+
+        // Winner takes all is the chip share, busted players get what's left.
+        assert_close(&icm_equities(&[3.0, 1.0], &[100.0]), &[75.0, 25.0]);
+        assert_close(&icm_equities(&[10.0, 0.0, 0.0], &[60.0, 30.0, 10.0]), &[60.0, 20.0, 20.0]);
+    }
+
+    #[test]
+    fn test_icm_decision() {
+        // On the bubble the middle stack folds a coin flip the chips say to call.
+        let spot = IcmSpot {
+            stacks:   vec![0.0, 2900.0, 1900.0, 900.0],
+            invested: vec![4000.0, 100.0, 100.0, 100.0],
+            payouts:  vec![50.0, 30.0, 20.0],
+            hero:     2,
+            villain:  0,
+        };
+        let results = EquityResults { wins: vec![0.55, 0.45], ties: vec![0.0, 0.0], total: 1.0 };
+        let decision = spot.decide(&results).unwrap();
+        assert!(decision.chip_required < 0.55);
+        assert!(decision.required_equity > 0.55);
+        assert!(!decision.should_call());
+
+        let lookup = load_lookup_table("./data/lookup_table.bin").unwrap();
+        let ranges = [Range::from_str("AA").unwrap(), Range::from_str("KK").unwrap()];
+        let decision = icm_call(&spot, ranges, Board::default(), &lookup).unwrap();
+        assert!((decision.equity - 0.8195).abs() < 0.001);
+        assert!(decision.should_call());
+
+        let invalid = IcmSpot { villain: 2, ..spot };
+        assert!(invalid.decide(&results).is_err());
+        let invalid = IcmSpot { stacks: vec![100.0; 21], invested: vec![10.0; 21], ..invalid };
+        assert!(matches!(invalid.decide(&results), Err(Error::InvalidIcmSpot(_))));
+    }
+
+    #[test]
+    fn test_villain_behind() {
+        // The villain keeps the chips behind their bet whether hero wins or loses.
+        let spot = IcmSpot {
+            stacks:   vec![1000.0, 500.0, 500.0],
+            invested: vec![500.0, 0.0, 0.0],
+            payouts:  vec![50.0, 30.0, 20.0],
+            hero:     1,
+            villain:  0,
+        };
+        let won = EquityResults { wins: vec![1.0, 0.0], ties: vec![0.0, 0.0], total: 1.0 };
+        let decision = spot.decide(&won).unwrap();
+        assert!((decision.call_ev - icm_equities(&[1000.0, 1000.0, 500.0], &spot.payouts)[1]).abs() < 1e-9);
+        let lost = EquityResults { wins: vec![0.0, 1.0], ties: vec![0.0, 0.0], total: 1.0 };
+        let decision = spot.decide(&lost).unwrap();
+        assert!((decision.call_ev - icm_equities(&[2000.0, 0.0, 500.0], &spot.payouts)[1]).abs() < 1e-9);
+    }
+}
@@ -2,9 +2,13 @@
 pub mod card;
 pub mod deck;
 pub mod hand;
+pub mod history;
 pub mod board;
 pub mod evaluate;
 pub mod equity;
+pub mod ev;
+pub mod icm;
+pub mod solver;
 pub mod range;
 pub mod tables;
 pub mod error;
@@ -1,4 +1,5 @@
 use std::{ops::Deref, fmt::Debug};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use crate::{hand::Hand, card::*};
 
 mod parser;
@@ -200,9 +201,44 @@ pub fn offsuit_idxs(rank_1: Rank, rank_2: Rank) -> Vec<usize> {
     idxs
 }
 
+// Ranges serialize as their name and the combos in them, so ranges built hand by hand keep
+// their combos.
+#[derive(Serialize, Deserialize)]
+struct RangeCombos {
+    name:   String,
+    combos: Vec<Hand>,
+}
+
+impl Serialize for Range {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RangeCombos { name: self.name.clone(), combos: self.hand_combos(0) }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Range {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let combos = RangeCombos::deserialize(deserializer)?;
+        let mut range = Range { name: combos.name, ..Range::default() };
+        combos.combos.iter().for_each(|hand| range.set_hand(hand, true));
+        Ok(range)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     
+    #[test]
+    fn test_serde() {
+        let range = super::Range::from_str("QQ+, AKs").unwrap();
+        let json = serde_json::to_string(&range).unwrap();
+        assert!(json.starts_with(r#"{"name":"QQ+, AKs","combos":["#));
+        let parsed = serde_json::from_str::<super::Range>(&json).unwrap();
+        assert_eq!(parsed.name(), "QQ+, AKs");
+        assert_eq!(parsed.hand_combos(0), range.hand_combos(0));
+        assert_eq!(parsed.hand_combos(0).len(), 22);
+    }
+
+    
     #[test]
     fn test_parse_range() {
 
@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use crate::{error::Error, evaluate::BoardState, prelude::*};
+use super::{tree::{GameTree, Node, TreeConfig}, Solution};
+
+pub struct SolverParams<'a> {
+    // Out of position first, in position second.
+    pub ranges:                [Range; 2],
+    pub board:                 Board,
+    pub pot:                   f64,
+    // Effective stack behind at the start of the tree.
+    pub stack:                 f64,
+    pub tree:                  TreeConfig,
+    pub lookup:                &'a [i32],
+    pub iterations:            u32,
+    // Stops early once exploitability, as a percentage of the pot, is at most this.
+    pub target_exploitability: f64,
+}
+
+// Discounted CFR parameters, positive regrets are discounted by t^α / (t^α + 1), negative regrets
+// by t^β / (t^β + 1) and the average strategy weights iterations by t^γ.
+const ALPHA: f64 = 1.5;
+const BETA: f64 = 0.0;
+const GAMMA: f64 = 2.0;
+
+// Iterations between exploitability checks.
+const CHECK_INTERVAL: u32 = 10;
+
+// Solves the heads-up game from the start of the board's street with discounted CFR, alternating
+// updates between the players.  Showdowns are ranked once per board up front, each traversal
+// then only compares the players' hands in order of strength.
+pub fn solve(params: SolverParams) -> Result<Solution> {
+    let game = Game::new(&params)?;
+    let mut nodes = game.node_states();
+    let mut iterations = 0;
+    let mut exploitability = f64::INFINITY;
+
+    for t in 1..=params.iterations {
+        for player in 0..2 {
+            let reach = vec![1.0; game.hands[1 - player].len()];
+            let mut pass = Pass::Train { nodes: &mut nodes, t: t as f64 };
+            game.cfv(game.tree.root(), game.board, player, &reach, &mut pass);
+        }
+        iterations = t;
+
+        if t % CHECK_INTERVAL == 0 || t == params.iterations {
+            let strategies = average_strategies(&nodes);
+            exploitability = game.exploitability(&strategies);
+            if exploitability <= params.target_exploitability {
+                break;
+            }
+        }
+    }
+
+    let strategies = average_strategies(&nodes);
+    Ok(Solution { game, strategies, iterations, exploitability })
+}
+
+// Regrets and average strategy sums of an action node, indexed action * hands + hand.
+#[derive(Debug, Clone)]
+pub(super) struct NodeState {
+    num_actions:  usize,
+    regrets:      Vec<f64>,
+    strategy_sum: Vec<f64>,
+}
+
+pub(super) enum Pass<'a> {
+    Train { nodes: &'a mut [NodeState], t: f64 },
+    Average(&'a [Vec<f64>]),
+    BestResponse(&'a [Vec<f64>]),
+}
+
+// Each player's hands still in play on a full board, weakest first.
+type Showdown = [Vec<(u16, usize)>; 2];
+
+pub(super) struct Game {
+    pub(super) tree:  GameTree,
+    pot:              f64,
+    pub(super) board: u64,
+    pub(super) hands: [Vec<Hand>; 2],
+    // Index of the same combo among the other player's hands.
+    same_hand:        [Vec<Option<usize>>; 2],
+    showdowns:        HashMap<u64, Showdown>,
+}
+
+impl Game {
+    fn new(params: &SolverParams) -> Result<Self> {
+        let board = params.board.mask();
+        let hands = [0, 1].map(|player| params.ranges[player].hand_combos(board));
+        if hands.iter().any(|hands| hands.is_empty()) {
+            return Err(Error::NoValidCombos);
+        }
+
+        let same_hand = [0, 1].map(|player| {
+            let mut other = vec![None; 1326];
+            hands[1 - player].iter().enumerate().for_each(|(i, hand)| other[hand.idx()] = Some(i));
+            hands[player].iter().map(|hand| other[hand.idx()]).collect()
+        });
+
+        let tree = GameTree::new(&params.board, params.pot, params.stack, &params.tree)?;
+        let mut showdowns = HashMap::new();
+        rank_showdowns(tree.root(), board, &hands, params.lookup, &mut showdowns);
+
+        Ok(Self {
+            tree,
+            pot: params.pot,
+            board,
+            hands,
+            same_hand,
+            showdowns,
+        })
+    }
+
+    fn node_states(&self) -> Vec<NodeState> {
+        let mut states = vec![None; self.tree.action_nodes()];
+        self.collect_states(self.tree.root(), &mut states);
+        states.into_iter().map(Option::unwrap).collect()
+    }
+
+    fn collect_states(&self, node: &Node, states: &mut [Option<NodeState>]) {
+        match node {
+            Node::Action { player, index, actions, children } => {
+                let len = actions.len() * self.hands[*player].len();
+                states[*index] = Some(NodeState {
+                    num_actions:  actions.len(),
+                    regrets:      vec![0.0; len],
+                    strategy_sum: vec![0.0; len],
+                });
+                children.iter().for_each(|child| self.collect_states(child, states));
+            },
+            Node::Chance { children, .. } => {
+                children.iter().for_each(|child| self.collect_states(child, states));
+            },
+            _ => {},
+        }
+    }
+
+    // Counterfactual value of each of the player's hands, the chips they end the hand with
+    // weighted by the opponent's reach of each hand they can be up against.
+    pub(super) fn cfv(&self, node: &Node, board: u64, player: usize, reach: &[f64], pass: &mut Pass) -> Vec<f64> {
+        let num_hands = self.hands[player].len();
+        match node {
+            Node::Fold { player: folder, invested } => {
+                let value = if *folder == player { -invested } else { self.pot + invested };
+                self.opponent_reach(player, reach).into_iter().map(|reach| reach * value).collect()
+            },
+
+            Node::Showdown { invested } => self.showdown(board, player, reach, *invested),
+
+            Node::Chance { cards, children } => {
+                // Of the cards left, the four in the players' hands can't come.
+                let weight = 1.0 / (cards.len() - 4) as f64;
+                let mut values = vec![0.0; num_hands];
+                for (card, child) in cards.iter().zip(children) {
+                    let mask = 1 << card.0;
+                    let child_reach = self.hands[1 - player].iter().zip(reach)
+                        .map(|(hand, &reach)| if hand.mask() & mask == 0 { reach } else { 0.0 })
+                        .collect::<Vec<f64>>();
+                    let child_values = self.cfv(child, board | mask, player, &child_reach, pass);
+                    for ((value, child_value), hand) in values.iter_mut().zip(child_values).zip(&self.hands[player]) {
+                        if hand.mask() & mask == 0 {
+                            *value += weight * child_value;
+                        }
+                    }
+                }
+                values
+            },
+
+            Node::Action { player: actor, index, actions, children } => {
+                let actor_hands = self.hands[*actor].len();
+                let strategy = match pass {
+                    Pass::Train { nodes, .. } => current_strategy(&nodes[*index].regrets, actions.len()),
+                    Pass::Average(strategies) | Pass::BestResponse(strategies) => strategies[*index].clone(),
+                };
+
+                if *actor == player {
+                    let child_values = children.iter()
+                        .map(|child| self.cfv(child, board, player, reach, pass))
+                        .collect::<Vec<Vec<f64>>>();
+
+                    let mut values = vec![0.0; num_hands];
+                    if let Pass::BestResponse(_) = pass {
+                        for (h, value) in values.iter_mut().enumerate() {
+                            *value = child_values.iter().map(|v| v[h]).fold(f64::NEG_INFINITY, f64::max);
+                        }
+                        return values;
+                    }
+
+                    for (a, child) in child_values.iter().enumerate() {
+                        for (h, value) in values.iter_mut().enumerate() {
+                            *value += strategy[a * num_hands + h] * child[h];
+                        }
+                    }
+
+                    if let Pass::Train { nodes, t } = pass {
+                        let positive = (*t - 1.0).powf(ALPHA) / ((*t - 1.0).powf(ALPHA) + 1.0);
+                        let negative = (*t - 1.0).powf(BETA) / ((*t - 1.0).powf(BETA) + 1.0);
+                        let regrets = &mut nodes[*index].regrets;
+                        for (a, child) in child_values.iter().enumerate() {
+                            for h in 0..num_hands {
+                                let regret = &mut regrets[a * num_hands + h];
+                                *regret *= if *regret > 0.0 { positive } else { negative };
+                                *regret += child[h] - values[h];
+                            }
+                        }
+                    }
+                    values
+                } else {
+                    if let Pass::Train { nodes, t } = pass {
+                        let discount = ((*t - 1.0) / *t).powf(GAMMA);
+                        let strategy_sum = &mut nodes[*index].strategy_sum;
+                        for (i, sum) in strategy_sum.iter_mut().enumerate() {
+                            *sum = *sum * discount + reach[i % actor_hands] * strategy[i];
+                        }
+                    }
+
+                    let mut values = vec![0.0; num_hands];
+                    for (a, child) in children.iter().enumerate() {
+                        let child_reach = reach.iter().enumerate()
+                            .map(|(h, reach)| reach * strategy[a * actor_hands + h])
+                            .collect::<Vec<f64>>();
+                        let child_values = self.cfv(child, board, player, &child_reach, pass);
+                        values.iter_mut().zip(child_values).for_each(|(value, child)| *value += child);
+                    }
+                    values
+                }
+            },
+        }
+    }
+
+    // Total reach of the opponent's hands that don't share a card with each of the player's.
+    pub(super) fn opponent_reach(&self, player: usize, reach: &[f64]) -> Vec<f64> {
+        let mut total = 0.0;
+        let mut cards = [0.0; 52];
+        for (hand, &reach) in self.hands[1 - player].iter().zip(reach) {
+            total += reach;
+            cards[hand.0.0 as usize] += reach;
+            cards[hand.1.0 as usize] += reach;
+        }
+
+        self.hands[player].iter().zip(&self.same_hand[player]).map(|(hand, same)| {
+            total - cards[hand.0.0 as usize] - cards[hand.1.0 as usize] + same.map_or(0.0, |i| reach[i])
+        }).collect()
+    }
+
+    // Sweeps both players' hands in order of strength, keeping the reach of the opponent's weaker
+    // hands and of those holding each card, so a hand's wins are the weaker reach less the hands
+    // it blocks.  Losses are the same from the top, ties are what is left.
+    fn showdown(&self, board: u64, player: usize, reach: &[f64], invested: f64) -> Vec<f64> {
+        let showdown = &self.showdowns[&board];
+        let (mine, theirs) = (&showdown[player], &showdown[1 - player]);
+        let hands = &self.hands[player];
+        let opponent_hands = &self.hands[1 - player];
+
+        let mut wins = vec![0.0; hands.len()];
+        let mut losses = vec![0.0; hands.len()];
+        for (outcome, ascending) in [(&mut wins, true), (&mut losses, false)] {
+            let mut total = 0.0;
+            let mut cards = [0.0; 52];
+            let mut j = 0;
+            for k in 0..mine.len() {
+                let (rank, h) = if ascending { mine[k] } else { mine[mine.len() - 1 - k] };
+                while j < theirs.len() {
+                    let (other_rank, o) = if ascending { theirs[j] } else { theirs[theirs.len() - 1 - j] };
+                    if (ascending && other_rank >= rank) || (!ascending && other_rank <= rank) {
+                        break;
+                    }
+                    let hand = opponent_hands[o];
+                    total += reach[o];
+                    cards[hand.0.0 as usize] += reach[o];
+                    cards[hand.1.0 as usize] += reach[o];
+                    j += 1;
+                }
+                let hand = hands[h];
+                outcome[h] = total - cards[hand.0.0 as usize] - cards[hand.1.0 as usize];
+            }
+        }
+
+        let totals = self.opponent_reach(player, reach);
+        let tie = self.pot / 2.0;
+        (0..hands.len()).map(|h| {
+            if hands[h].mask() & board != 0 {
+                return 0.0;
+            }
+            let ties = totals[h] - wins[h] - losses[h];
+            wins[h] * (self.pot + invested) - losses[h] * invested + ties * tie
+        }).collect()
+    }
+
+    // Mean chips each player ends with when best responding to the other's strategy, less the
+    // pot, halved and as a percentage of the pot.  Zero at a Nash equilibrium.
+    fn exploitability(&self, strategies: &[Vec<f64>]) -> f64 {
+        let best_responses = [0, 1].map(|player| self.value(player, &mut Pass::BestResponse(strategies)));
+        (best_responses[0] + best_responses[1] - self.pot) / 2.0 / self.pot * 100.0
+    }
+
+    // Expected chips of the player over all pairs of hands.
+    pub(super) fn value(&self, player: usize, pass: &mut Pass) -> f64 {
+        let reach = vec![1.0; self.hands[1 - player].len()];
+        let values = self.cfv(self.tree.root(), self.board, player, &reach, pass);
+        let pairs = self.opponent_reach(player, &reach).iter().sum::<f64>();
+        values.iter().sum::<f64>() / pairs
+    }
+}
+
+fn rank_showdowns(node: &Node, board: u64, hands: &[Vec<Hand>; 2], lookup: &[i32], showdowns: &mut HashMap<u64, Showdown>) {
+    match node {
+        Node::Showdown { .. } => {
+            showdowns.entry(board).or_insert_with(|| {
+                let cards = (0..52).filter(|i| board >> i & 1 == 1).map(Card).collect::<Vec<Card>>();
+                let state = BoardState::new(&cards, lookup);
+                [0, 1].map(|player| {
+                    let mut ranked = hands[player].iter().enumerate()
+                        .filter(|(_, hand)| hand.mask() & board == 0)
+                        .map(|(i, hand)| (state.eval_hand(hand, lookup), i))
+                        .collect::<Vec<(u16, usize)>>();
+                    ranked.sort_unstable();
+                    ranked
+                })
+            });
+        },
+        Node::Chance { cards, children } => {
+            for (card, child) in cards.iter().zip(children) {
+                rank_showdowns(child, board | 1 << card.0, hands, lookup, showdowns);
+            }
+        },
+        Node::Action { children, .. } => {
+            for child in children {
+                rank_showdowns(child, board, hands, lookup, showdowns);
+            }
+        },
+        Node::Fold { .. } => {},
+    }
+}
+
+// Regret matching, uniform where no action has positive regret.
+fn current_strategy(regrets: &[f64], num_actions: usize) -> Vec<f64> {
+    normalize(regrets.iter().map(|regret| regret.max(0.0)).collect(), num_actions)
+}
+
+fn average_strategies(nodes: &[NodeState]) -> Vec<Vec<f64>> {
+    nodes.iter().map(|node| normalize(node.strategy_sum.clone(), node.num_actions)).collect()
+}
+
+// Normalizes each hand's weights over the actions.
+fn normalize(mut weights: Vec<f64>, num_actions: usize) -> Vec<f64> {
+    let num_hands = weights.len() / num_actions;
+    for h in 0..num_hands {
+        let total = (0..num_actions).map(|a| weights[a * num_hands + h]).sum::<f64>();
+        for a in 0..num_actions {
+            let weight = &mut weights[a * num_hands + h];
+            *weight = if total > 0.0 { *weight / total } else { 1.0 / num_actions as f64 };
+        }
+    }
+    weights
+}
@@ -0,0 +1,11 @@
+mod cfr;
+mod push_fold;
+mod solution;
+mod tree;
+#[cfg(test)]
+mod tests;
+
+pub use cfr::{solve, SolverParams};
+pub use push_fold::{position_name, push_fold, range_share, PushFoldParams, PushFoldSolution};
+pub use solution::{ComboStrategy, NodeStrategy, Solution};
+pub use tree::{Action, BetSizes, GameTree, Node, NodeCount, TreeConfig};
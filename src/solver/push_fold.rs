@@ -0,0 +1,219 @@
+use crate::{equity::{hand_class_combos, range_from_classes, PreflopEquities, NUM_CLASSES}, error::Error, prelude::*};
+
+pub struct PushFoldParams<'a> {
+    // Stacks in order of action with the blinds last, heads-up the small blind acts first.
+    pub stacks:      Vec<f64>,
+    pub small_blind: f64,
+    pub big_blind:   f64,
+    pub ante:        f64,
+    pub equities:    &'a PreflopEquities,
+    pub iterations:  u32,
+}
+
+// Frequencies of each starting hand class, see hand_class for the order.
+#[derive(Debug, Clone)]
+pub struct PushFoldSolution {
+    // Jamming when folded to, the big blind's never does.
+    pub jam:  Vec<Vec<f64>>,
+    // call[jammer][caller], calling a jam with everyone in between folded.
+    pub call: Vec<Vec<Vec<f64>>>,
+}
+
+impl PushFoldSolution {
+    pub fn jam_range(&self, player: usize) -> Range {
+        range_from_classes(&self.jam[player])
+    }
+
+    pub fn call_range(&self, jammer: usize, caller: usize) -> Range {
+        range_from_classes(&self.call[jammer][caller])
+    }
+}
+
+// Names from the big blind back, for up to 10 players.
+const POSITIONS: [&str; 10] = ["BB", "SB", "BTN", "CO", "HJ", "LJ", "MP", "UTG+2", "UTG+1", "UTG"];
+
+pub fn position_name(player: usize, num_players: usize) -> &'static str {
+    POSITIONS[num_players - 1 - player]
+}
+
+// Nash jam and call ranges by fictitious play, each iteration moving every strategy towards the
+// best response to the others.  Once one player calls a jam the rest fold, so there are no
+// multiway all-ins, and card removal only counts the hand of the player deciding.
+pub fn push_fold(params: PushFoldParams) -> Result<PushFoldSolution> {
+    let num_players = params.stacks.len();
+    if !(2..=POSITIONS.len()).contains(&num_players) {
+        return Err(Error::InvalidPlayerCount(num_players));
+    }
+
+    let game = PushFoldGame::new(&params);
+    let mut solution = PushFoldSolution {
+        jam:  vec![vec![1.0; NUM_CLASSES]; num_players],
+        call: vec![vec![vec![0.5; NUM_CLASSES]; num_players]; num_players],
+    };
+    solution.jam[num_players - 1] = vec![0.0; NUM_CLASSES];
+
+    for t in 1..=params.iterations {
+        let best = game.best_response(&solution);
+        let step = 1.0 / (t as f64 + 1.0);
+        let update = |current: &mut Vec<f64>, best: &Vec<f64>| {
+            current.iter_mut().zip(best).for_each(|(c, b)| *c += (b - *c) * step);
+        };
+        for jammer in 0..num_players - 1 {
+            update(&mut solution.jam[jammer], &best.jam[jammer]);
+            for caller in (jammer + 1)..num_players {
+                update(&mut solution.call[jammer][caller], &best.call[jammer][caller]);
+            }
+        }
+    }
+    Ok(solution)
+}
+
+struct PushFoldGame<'a> {
+    stacks:   Vec<f64>,
+    posted:   Vec<f64>,
+    dead:     f64,
+    equities: &'a PreflopEquities,
+    // Combos an opponent can hold alongside each class.
+    combos:   Vec<f64>,
+}
+
+impl<'a> PushFoldGame<'a> {
+    fn new(params: &PushFoldParams<'a>) -> Self {
+        let num_players = params.stacks.len();
+        let posted = params.stacks.iter().enumerate().map(|(player, &stack)| {
+            let blind = match num_players - 1 - player {
+                0 => params.big_blind,
+                1 => params.small_blind,
+                _ => 0.0,
+            };
+            f64::min(stack, params.ante + blind)
+        }).collect::<Vec<f64>>();
+
+        let all = vec![1.0; NUM_CLASSES];
+        Self {
+            stacks:   params.stacks.clone(),
+            dead:     posted.iter().sum(),
+            posted,
+            equities: params.equities,
+            combos:   (0..NUM_CLASSES).map(|class| params.equities.equity_vs_range(class, &all).1).collect(),
+        }
+    }
+
+    // Chips the caller and jammer end with when they get it in, for the equity of one of them.
+    fn showdown(&self, player: usize, jammer: usize, caller: usize, equity: f64) -> f64 {
+        let all_in = f64::min(self.stacks[jammer], self.stacks[caller]);
+        let pot = 2.0 * all_in + self.dead - self.posted[jammer] - self.posted[caller];
+        self.stacks[player] - all_in + equity * pot
+    }
+
+    // Pure best response of every decision to the current strategies.
+    fn best_response(&self, current: &PushFoldSolution) -> PushFoldSolution {
+        let num_players = self.stacks.len();
+        let mut best = PushFoldSolution {
+            jam:  vec![vec![0.0; NUM_CLASSES]; num_players],
+            call: vec![vec![vec![0.0; NUM_CLASSES]; num_players]; num_players],
+        };
+
+        for jammer in 0..num_players - 1 {
+            for class in 0..NUM_CLASSES {
+                let fold = self.stacks[jammer] - self.posted[jammer];
+                let mut jam = 0.0;
+                // Chance no one before the caller has called.
+                let mut uncalled = 1.0;
+                for caller in (jammer + 1)..num_players {
+                    let (equity, combos) = self.equities.equity_vs_range(class, &current.call[jammer][caller]);
+                    let calls = combos / self.combos[class];
+                    if calls > 0.0 {
+                        jam += uncalled * calls * self.showdown(jammer, jammer, caller, equity);
+                    }
+                    uncalled *= 1.0 - calls;
+                }
+                jam += uncalled * (fold + self.dead);
+                best.jam[jammer][class] = if jam > fold { 1.0 } else { 0.0 };
+            }
+
+            for caller in (jammer + 1)..num_players {
+                for class in 0..NUM_CLASSES {
+                    let (equity, combos) = self.equities.equity_vs_range(class, &current.jam[jammer]);
+                    let fold = self.stacks[caller] - self.posted[caller];
+                    let call = if combos > 0.0 { self.showdown(caller, jammer, caller, equity) } else { fold };
+                    best.call[jammer][caller][class] = if call > fold { 1.0 } else { 0.0 };
+                }
+            }
+        }
+        best
+    }
+}
+
+// Share of all 1,326 combos the classes make up, weighted by their frequencies.
+pub fn range_share(frequencies: &[f64]) -> f64 {
+    frequencies.iter().enumerate()
+        .map(|(class, frequency)| frequency * hand_class_combos(class).len() as f64)
+        .sum::<f64>() / 1326.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equity::hand_class_name;
+
+    // Equities from the Chen score of each class, enough to check the solver without the table.
+    fn equities() -> PreflopEquities {
+        let scores = (0..NUM_CLASSES).map(|class| hand_class_combos(class)[0].chen_score() as f64).collect::<Vec<f64>>();
+        let mut equities = Vec::with_capacity(NUM_CLASSES * NUM_CLASSES);
+        for a in &scores {
+            for b in &scores {
+                equities.push(0.5 + 0.35 * ((a - b) / 6.0).tanh());
+            }
+        }
+        PreflopEquities::from_vec(equities)
+    }
+
+    fn solve(stacks: Vec<f64>, equities: &PreflopEquities) -> PushFoldSolution {
+        push_fold(PushFoldParams {
+            stacks,
+            small_blind: 0.5,
+            big_blind: 1.0,
+            ante: 0.0,
+            equities,
+            iterations: 300,
+        }).unwrap()
+    }
+
+    #[test]
+    fn test_push_fold_heads_up() {
+        let equities = equities();
+        let short = solve(vec![5.0, 5.0], &equities);
+        let deep = solve(vec![25.0, 25.0], &equities);
+
+        let class = |name: &str| (0..NUM_CLASSES).find(|&class| hand_class_name(class) == name).unwrap();
+        assert!(short.jam[0][class("AA")] > 0.99 && deep.jam[0][class("AA")] > 0.99);
+        assert!(deep.call[0][1][class("AA")] > 0.99);
+        assert!(deep.call[0][1][class("72o")] < 0.01);
+
+        // Shorter stacks jam and call wider, and the jammer is wider than the caller.
+        assert!(range_share(&short.jam[0]) > range_share(&deep.jam[0]));
+        assert!(range_share(&short.call[0][1]) > range_share(&deep.call[0][1]));
+        assert!(range_share(&deep.jam[0]) > range_share(&deep.call[0][1]));
+        assert!(deep.jam_range(0).get_hand(&Hand::from_str("As Ad").unwrap()));
+    }
+
+    #[test]
+    fn test_push_fold_multiway() {
+        let equities = equities();
+        let solution = solve(vec![10.0; 3], &equities);
+        assert_eq!(position_name(0, 3), "BTN");
+        assert!(solution.jam[2].iter().all(|&jam| jam == 0.0));
+        // With two players left to act behind the button jams tighter than the small blind.
+        assert!(range_share(&solution.jam[0]) < range_share(&solution.jam[1]));
+
+        assert!(push_fold(PushFoldParams {
+            stacks: vec![10.0],
+            small_blind: 0.5,
+            big_blind: 1.0,
+            ante: 0.0,
+            equities: &equities,
+            iterations: 10,
+        }).is_err());
+    }
+}
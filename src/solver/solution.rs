@@ -0,0 +1,149 @@
+use prettytable::{Table, Row, Cell};
+use crate::prelude::*;
+use super::{cfr::{Game, Pass}, tree::{Action, GameTree, Node}};
+
+// Average strategies of a solved game.
+pub struct Solution {
+    pub(super) game:       Game,
+    // Per action node, indexed action * hands + hand.
+    pub(super) strategies: Vec<Vec<f64>>,
+    pub iterations:        u32,
+    // Percentage of the pot a best responding opponent gains on average.
+    pub exploitability:    f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ComboStrategy {
+    pub hand:        Hand,
+    // How often the player's earlier actions bring the combo to the node.
+    pub weight:      f64,
+    pub frequencies: Vec<f64>,
+    // Chips the combo ends the hand with on average, from the node and after each action.
+    pub ev:          f64,
+    pub action_evs:  Vec<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeStrategy {
+    pub player:  usize,
+    pub actions: Vec<Action>,
+    pub combos:  Vec<ComboStrategy>,
+}
+
+impl Solution {
+    pub fn tree(&self) -> &GameTree {
+        &self.game.tree
+    }
+
+    pub fn hands(&self, player: usize) -> &[Hand] {
+        &self.game.hands[player]
+    }
+
+    // Chips each player ends the hand with on average, the two adding up to the pot.
+    pub fn ev(&self) -> [f64; 2] {
+        [0, 1].map(|player| self.game.value(player, &mut Pass::Average(&self.strategies)))
+    }
+
+    pub fn root_strategy(&self) -> NodeStrategy {
+        self.strategy(&[], &[]).unwrap()
+    }
+
+    // Strategy at the action node reached by the actions, taking the next of the cards at each
+    // chance node.  None if the path leaves the tree or doesn't end at an action node.
+    pub fn strategy(&self, history: &[Action], cards: &[Card]) -> Option<NodeStrategy> {
+        let game = &self.game;
+        let mut node = game.tree.root();
+        let mut board = game.board;
+        let mut reach = [0, 1].map(|player| vec![1.0; game.hands[player].len()]);
+        let mut history = history.iter();
+        let mut cards = cards.iter();
+
+        loop {
+            match node {
+                Node::Action { player, index, actions, children } => {
+                    let Some(action) = history.next() else { break };
+                    let a = actions.iter().position(|a| a == action)?;
+                    let num_hands = game.hands[*player].len();
+                    let strategy = &self.strategies[*index];
+                    for (h, reach) in reach[*player].iter_mut().enumerate() {
+                        *reach *= strategy[a * num_hands + h];
+                    }
+                    node = &children[a];
+                },
+                Node::Chance { cards: dealt, children } => {
+                    let card = cards.next()?;
+                    let i = dealt.iter().position(|c| c == card)?;
+                    for (hands, reach) in game.hands.iter().zip(reach.iter_mut()) {
+                        for (hand, reach) in hands.iter().zip(reach.iter_mut()) {
+                            if hand.mask() & 1 << card.0 != 0 {
+                                *reach = 0.0;
+                            }
+                        }
+                    }
+                    board |= 1 << card.0;
+                    node = &children[i];
+                },
+                _ => return None,
+            }
+        }
+
+        let Node::Action { player, index, actions, children } = node else { unreachable!() };
+        let player = *player;
+        let opponent_reach = &reach[1 - player];
+        let totals = game.opponent_reach(player, opponent_reach);
+        let action_values = children.iter()
+            .map(|child| game.cfv(child, board, player, opponent_reach, &mut Pass::Average(&self.strategies)))
+            .collect::<Vec<Vec<f64>>>();
+
+        let num_hands = game.hands[player].len();
+        let strategy = &self.strategies[*index];
+        let combos = game.hands[player].iter().enumerate()
+            .filter(|&(h, hand)| hand.mask() & board == 0 && reach[player][h] > 0.0 && totals[h] > 0.0)
+            .map(|(h, &hand)| {
+                let frequencies = (0..actions.len()).map(|a| strategy[a * num_hands + h]).collect::<Vec<f64>>();
+                let action_evs = action_values.iter().map(|values| values[h] / totals[h]).collect::<Vec<f64>>();
+                ComboStrategy {
+                    hand,
+                    weight: reach[player][h],
+                    ev: frequencies.iter().zip(&action_evs).map(|(f, ev)| f * ev).sum(),
+                    frequencies,
+                    action_evs,
+                }
+            })
+            .collect();
+
+        Some(NodeStrategy { player, actions: actions.clone(), combos })
+    }
+}
+
+impl NodeStrategy {
+    // Frequency of each action over the combos, weighted by how often they reach the node.
+    pub fn frequencies(&self) -> Vec<f64> {
+        let total = self.combos.iter().map(|combo| combo.weight).sum::<f64>();
+        (0..self.actions.len()).map(|a| {
+            self.combos.iter().map(|combo| combo.weight * combo.frequencies[a]).sum::<f64>() / total
+        }).collect()
+    }
+
+    // One row per combo with the frequency and EV of each action.
+    pub fn print(&self) {
+        let mut table = Table::new();
+        let mut header = vec![Cell::new("Hand"), Cell::new("EV")];
+        header.extend(self.actions.iter().map(|action| Cell::new(&action.to_string())));
+        table.add_row(Row::new(header));
+
+        let mut row = vec![Cell::new("All"), Cell::new("")];
+        row.extend(self.frequencies().iter().map(|frequency| Cell::new(&format!("{:.1}%", frequency * 100.0))));
+        table.add_row(Row::new(row));
+
+        for combo in &self.combos {
+            let mut row = vec![Cell::new(&format!("{}{}", combo.hand.0, combo.hand.1)), Cell::new(&format!("{:.2}", combo.ev))];
+            row.extend(combo.frequencies.iter().zip(&combo.action_evs).map(|(frequency, ev)| {
+                Cell::new(&format!("{:.1}% ({:.2})", frequency * 100.0, ev))
+            }));
+            table.add_row(Row::new(row));
+        }
+
+        table.printstd();
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{error::Error, evaluate::load_lookup_table, prelude::*};
+use super::{solve, Action, BetSizes, SolverParams, TreeConfig};
+
+const LOOKUP_PATH: &str = "./data/lookup_table.bin";
+
+fn params<'a>(ranges: [&str; 2], board: &str, stack: f64, bet_sizes: BetSizes, lookup: &'a [i32]) -> SolverParams<'a> {
+    SolverParams {
+        ranges: ranges.map(|range| Range::from_str(range).unwrap()),
+        board: Board::from_str(board).unwrap(),
+        pot: 100.0,
+        stack,
+        tree: TreeConfig::new(bet_sizes),
+        lookup,
+        iterations: 1000,
+        target_exploitability: 0.1,
+    }
+}
+
+#[test]
+fn test_solve_river_polarized() {
+    // Nuts and air against a bluff catcher, with a pot sized all-in as the only bet.  The bettor
+    // bets 1 bluff for every 2 value hands and the bluff catcher calls half the time.
+    let lookup = load_lookup_table(LOOKUP_PATH).unwrap();
+    let sizes = BetSizes { bet: vec![], raise: vec![] };
+    let solution = solve(params(["JTs,54s", "88"], "As Ks Qd 7h 2c", 100.0, sizes, &lookup)).unwrap();
+    assert!(solution.exploitability <= 0.1, "{}", solution.exploitability);
+
+    let ev = solution.ev();
+    assert!((ev[0] + ev[1] - 100.0).abs() < 1e-6);
+
+    let root = solution.root_strategy();
+    assert_eq!(root.actions, vec![Action::Check, Action::AllIn(100.0)]);
+    for combo in &root.combos {
+        let bet = combo.frequencies[1];
+        if combo.hand.high_card() == crate::card::Rank::Jack {
+            assert!(bet > 0.95, "{:?} bets {}", combo.hand, bet);
+        }
+    }
+    let bluffs = root.combos.iter()
+        .filter(|combo| combo.hand.high_card() == crate::card::Rank::Five)
+        .map(|combo| combo.frequencies[1])
+        .sum::<f64>();
+    assert!((bluffs - 2.0).abs() < 0.2, "{}", bluffs);
+
+    let facing = solution.strategy(&[Action::AllIn(100.0)], &[]).unwrap();
+    assert_eq!(facing.actions, vec![Action::Fold, Action::Call]);
+    assert!((facing.frequencies()[1] - 0.5).abs() < 0.05, "{:?}", facing.frequencies());
+}
+
+#[test]
+fn test_solve_turn() {
+    let lookup = load_lookup_table(LOOKUP_PATH).unwrap();
+    let mut params = params(["AA,KK,QQ,AKs,AKo,T9s", "JJ,TT,AQs,KQs,QJs"], "Ah Td 6c 2s", 200.0, BetSizes::default(), &lookup);
+    params.iterations = 100;
+    params.target_exploitability = 0.5;
+    let solution = solve(params).unwrap();
+    assert!(solution.exploitability <= 0.5, "{}", solution.exploitability);
+
+    let ev = solution.ev();
+    assert!((ev[0] + ev[1] - 100.0).abs() < 1e-6);
+
+    let root = solution.root_strategy();
+    assert_eq!(root.actions, vec![Action::Check, Action::Bet(33.0), Action::Bet(75.0), Action::AllIn(200.0)]);
+    for combo in &root.combos {
+        assert!((combo.frequencies.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
+    // Down a line through the river card.
+    let river = solution.strategy(&[Action::Check, Action::Check], &[Card::from_str("3h").unwrap()]).unwrap();
+    assert_eq!(river.player, 0);
+    assert!(river.combos.iter().all(|combo| combo.hand.mask() & Card::from_str("3h").unwrap().mask() == 0));
+    assert!(solution.strategy(&[Action::Check, Action::Check], &[Card::from_str("Ah").unwrap()]).is_none());
+}
+
+#[test]
+fn test_solve_tree_too_large() {
+    let lookup = load_lookup_table(LOOKUP_PATH).unwrap();
+    let mut params = params(["AA,KK", "QQ,JJ"], "Ah Td 6c 2s", 200.0, BetSizes::default(), &lookup);
+    params.tree.max_nodes = 100;
+    assert!(matches!(solve(params), Err(Error::TreeTooLarge(_, 100))));
+}
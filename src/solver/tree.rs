@@ -0,0 +1,473 @@
+use crate::{card::Card, deck::Deck, board::Board, error::{Error, Result}};
+
+// Bet, Raise and AllIn carry the player's total bet on the street once made.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Fold,
+    Check,
+    Call,
+    Bet(f64),
+    Raise(f64),
+    AllIn(f64),
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Fold => write!(f, "Fold"),
+            Action::Check => write!(f, "Check"),
+            Action::Call => write!(f, "Call"),
+            Action::Bet(amount) => write!(f, "Bet {:.1}", amount),
+            Action::Raise(amount) => write!(f, "Raise {:.1}", amount),
+            Action::AllIn(amount) => write!(f, "All-in {:.1}", amount),
+        }
+    }
+}
+
+// Bets as fractions of the pot, raises as fractions of the pot after calling.  Going all-in is
+// always an option, sizes that would put a player all-in are left out.
+#[derive(Debug, Clone)]
+pub struct BetSizes {
+    pub bet:   Vec<f64>,
+    pub raise: Vec<f64>,
+}
+
+impl Default for BetSizes {
+    fn default() -> Self {
+        Self { bet: vec![0.33, 0.75], raise: vec![1.0] }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TreeConfig {
+    pub flop:             BetSizes,
+    pub turn:             BetSizes,
+    pub river:            BetSizes,
+    // Raises allowed on a street after the first bet.
+    pub raise_cap:        u32,
+    // Bets and raises that would put in at least this fraction of the stack go all-in instead.
+    pub all_in_threshold: f64,
+    // Whether out of position may lead into the player who bet last on the street before.
+    pub donk_bets:        bool,
+    // Trees estimated to have more nodes than this are refused before building.
+    pub max_nodes:        u64,
+}
+
+impl TreeConfig {
+    // The same sizes on every street.
+    pub fn new(sizes: BetSizes) -> Self {
+        Self {
+            flop:             sizes.clone(),
+            turn:             sizes.clone(),
+            river:            sizes,
+            raise_cap:        3,
+            all_in_threshold: 0.67,
+            donk_bets:        false,
+            max_nodes:        50_000_000,
+        }
+    }
+
+    fn sizes(&self, board_len: usize) -> &BetSizes {
+        match board_len {
+            3 => &self.flop,
+            4 => &self.turn,
+            _ => &self.river,
+        }
+    }
+}
+
+impl Default for TreeConfig {
+    fn default() -> Self {
+        Self::new(BetSizes::default())
+    }
+}
+
+// Player 0 is out of position and acts first on each street.  Amounts are chips put in since the
+// start of the tree, on top of the starting pot.
+#[derive(Debug, Clone)]
+pub enum Node {
+    Action {
+        player:   usize,
+        // Index among the action nodes, in the order they were built.
+        index:    usize,
+        actions:  Vec<Action>,
+        children: Vec<Node>,
+    },
+    // The next board card, one child for each card left in the deck.
+    Chance {
+        cards:    Vec<Card>,
+        children: Vec<Node>,
+    },
+    Fold {
+        player:   usize,
+        invested: f64,
+    },
+    Showdown {
+        invested: f64,
+    },
+}
+
+impl Node {
+    pub fn children(&self) -> &[Node] {
+        match self {
+            Node::Action { children, .. } | Node::Chance { children, .. } => children,
+            _ => &[],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeCount {
+    pub action:   u64,
+    pub chance:   u64,
+    pub terminal: u64,
+}
+
+impl NodeCount {
+    pub fn total(&self) -> u64 {
+        self.action + self.chance + self.terminal
+    }
+
+    fn add(&mut self, other: NodeCount) {
+        self.action += other.action;
+        self.chance += other.chance;
+        self.terminal += other.terminal;
+    }
+
+    fn times(self, n: u64) -> Self {
+        Self { action: self.action * n, chance: self.chance * n, terminal: self.terminal * n }
+    }
+}
+
+// Betting and chance nodes for the rest of a heads-up hand, from the start of the board's street.
+#[derive(Debug, Clone)]
+pub struct GameTree {
+    root:         Node,
+    action_nodes: usize,
+    count:        NodeCount,
+}
+
+impl GameTree {
+    pub fn new(board: &Board, pot: f64, stack: f64, config: &TreeConfig) -> Result<Self> {
+        let cards = board.as_vec();
+        check_board(&cards)?;
+        check_spot(pot, stack, config)?;
+        let estimate = Self::estimate(board, pot, stack, config)?.total();
+        if estimate > config.max_nodes {
+            return Err(Error::TreeTooLarge(estimate, config.max_nodes));
+        }
+
+        let mut builder = Builder { pot, stack, config, action_nodes: 0 };
+        let root = builder.street(&cards, [0.0; 2], None);
+        let count = count(&root);
+        Ok(Self { root, action_nodes: builder.action_nodes, count })
+    }
+
+    // Size of the tree new would build, without building it.  Every card of a chance node leads
+    // to the same betting, so each street's nodes are only counted once.
+    pub fn estimate(board: &Board, pot: f64, stack: f64, config: &TreeConfig) -> Result<NodeCount> {
+        let cards = board.as_vec();
+        check_board(&cards)?;
+        check_spot(pot, stack, config)?;
+
+        let builder = Builder { pot, stack, config, action_nodes: 0 };
+        Ok(builder.count_street(cards.len(), [0.0; 2], None))
+    }
+
+    pub fn root(&self) -> &Node {
+        &self.root
+    }
+
+    pub fn action_nodes(&self) -> usize {
+        self.action_nodes
+    }
+
+    pub fn count(&self) -> NodeCount {
+        self.count
+    }
+}
+
+fn check_board(cards: &[Card]) -> Result<()> {
+    if cards.len() < 3 {
+        return Err(Error::InvalidTreeBoard(cards.len()));
+    }
+    Ok(())
+}
+
+// Sizes that put nothing in would let both players bet forever.
+fn check_spot(pot: f64, stack: f64, config: &TreeConfig) -> Result<()> {
+    if !(pot > 0.0 && pot.is_finite()) {
+        return Err(Error::InvalidTree("pot must be positive"));
+    }
+    if !(stack >= 0.0 && stack.is_finite()) {
+        return Err(Error::InvalidTree("stack can't be negative"));
+    }
+    let sizes = [&config.flop, &config.turn, &config.river].into_iter().flat_map(|sizes| sizes.bet.iter().chain(&sizes.raise));
+    if sizes.into_iter().any(|size| !(*size > 0.0 && size.is_finite())) {
+        return Err(Error::InvalidTree("bet and raise sizes must be positive"));
+    }
+    Ok(())
+}
+
+fn count(node: &Node) -> NodeCount {
+    let mut total = match node {
+        Node::Action { .. } => NodeCount { action: 1, ..Default::default() },
+        Node::Chance { .. } => NodeCount { chance: 1, ..Default::default() },
+        _ => NodeCount { terminal: 1, ..Default::default() },
+    };
+    node.children().iter().for_each(|child| total.add(count(child)));
+    total
+}
+
+#[derive(Debug, Clone, Copy)]
+struct State {
+    invested:    [f64; 2],
+    street_base: f64,
+    raises:      u32,
+    player:      usize,
+    checked:     bool,
+    // Who bet last on this street and on the street before.
+    aggressor:   Option<usize>,
+    previous:    Option<usize>,
+}
+
+enum Next {
+    Fold,
+    StreetEnd { invested: [f64; 2], aggressor: Option<usize> },
+    Act(State),
+}
+
+struct Builder<'a> {
+    pot:          f64,
+    stack:        f64,
+    config:       &'a TreeConfig,
+    action_nodes: usize,
+}
+
+impl<'a> Builder<'a> {
+    fn street_state(invested: [f64; 2], previous: Option<usize>) -> State {
+        State { invested, street_base: invested[0], raises: 0, player: 0, checked: false, aggressor: None, previous }
+    }
+
+    // The actions open to the player and where each leads.
+    fn options(&self, board_len: usize, state: &State) -> Vec<(Action, Next)> {
+        let player = state.player;
+        let opponent = 1 - player;
+        let pot = self.pot + state.invested[0] + state.invested[1];
+        let to_call = state.invested[opponent] - state.invested[player];
+        let remaining = self.stack - state.invested[player];
+        let sizes = self.config.sizes(board_len);
+
+        // Bets and raises with the chips they put in, those over the threshold replaced by all-in.
+        let mut bets = Vec::new();
+        let mut options = Vec::new();
+        if to_call > 0.0 {
+            options.push((Action::Fold, Next::Fold));
+            let mut invested = state.invested;
+            invested[player] = invested[opponent];
+            options.push((Action::Call, Next::StreetEnd { invested, aggressor: state.aggressor }));
+
+            if remaining > to_call && state.raises < self.config.raise_cap {
+                bets.extend(sizes.raise.iter().map(|size| to_call + size * (pot + to_call)));
+                bets.push(remaining);
+            }
+        } else {
+            if state.checked || player == 1 {
+                options.push((Action::Check, Next::StreetEnd { invested: state.invested, aggressor: None }));
+            } else {
+                options.push((Action::Check, Next::Act(State { player: opponent, checked: true, ..*state })));
+            }
+
+            let donk = player == 0 && state.previous == Some(1);
+            if remaining > 0.0 && (self.config.donk_bets || !donk) {
+                bets.extend(sizes.bet.iter().map(|size| size * pot));
+                bets.push(remaining);
+            }
+        }
+
+        let all_in_at = self.config.all_in_threshold * self.stack;
+        let mut amounts = bets.into_iter()
+            .map(|amount| if state.invested[player] + amount >= all_in_at { remaining } else { amount.min(remaining) })
+            .filter(|&amount| amount > 0.0)
+            .collect::<Vec<f64>>();
+        amounts.sort_by(f64::total_cmp);
+        amounts.dedup();
+
+        for amount in amounts {
+            let street_bet = state.invested[player] + amount - state.street_base;
+            let action = if amount == remaining {
+                Action::AllIn(street_bet)
+            } else if to_call > 0.0 {
+                Action::Raise(street_bet)
+            } else {
+                Action::Bet(street_bet)
+            };
+
+            let mut next = *state;
+            next.invested[player] += amount;
+            next.player = opponent;
+            next.aggressor = Some(player);
+            if to_call > 0.0 {
+                next.raises += 1;
+            }
+            options.push((action, Next::Act(next)));
+        }
+        options
+    }
+
+    fn street(&mut self, board: &[Card], invested: [f64; 2], previous: Option<usize>) -> Node {
+        self.action(board, Self::street_state(invested, previous))
+    }
+
+    fn action(&mut self, board: &[Card], state: State) -> Node {
+        let index = self.action_nodes;
+        self.action_nodes += 1;
+
+        let mut actions = Vec::new();
+        let mut children = Vec::new();
+        for (action, next) in self.options(board.len(), &state) {
+            actions.push(action);
+            children.push(match next {
+                Next::Fold => Node::Fold { player: state.player, invested: state.invested[state.player] },
+                Next::StreetEnd { invested, aggressor } => self.street_end(board, invested, aggressor),
+                Next::Act(next) => self.action(board, next),
+            });
+        }
+
+        Node::Action { player: state.player, index, actions, children }
+    }
+
+    // Showdown on the river, otherwise the next card and the next street.  Once a player is all-in
+    // the rest of the board is dealt without further action.
+    fn street_end(&mut self, board: &[Card], invested: [f64; 2], aggressor: Option<usize>) -> Node {
+        if board.len() == 5 {
+            return Node::Showdown { invested: invested[0] };
+        }
+
+        let mut deck = Deck::new();
+        board.iter().for_each(|card| deck.remove(card));
+        let cards = (0..deck.len()).map(|i| deck[i]).collect::<Vec<Card>>();
+
+        let all_in = invested[0] >= self.stack;
+        let children = cards.iter().map(|&card| {
+            let mut board = board.to_vec();
+            board.push(card);
+            if all_in {
+                self.street_end(&board, invested, aggressor)
+            } else {
+                self.street(&board, invested, aggressor)
+            }
+        }).collect();
+
+        Node::Chance { cards, children }
+    }
+
+    fn count_street(&self, board_len: usize, invested: [f64; 2], previous: Option<usize>) -> NodeCount {
+        self.count_action(board_len, Self::street_state(invested, previous))
+    }
+
+    fn count_action(&self, board_len: usize, state: State) -> NodeCount {
+        let mut total = NodeCount { action: 1, ..Default::default() };
+        for (_, next) in self.options(board_len, &state) {
+            total.add(match next {
+                Next::Fold => NodeCount { terminal: 1, ..Default::default() },
+                Next::StreetEnd { invested, aggressor } => self.count_street_end(board_len, invested, aggressor),
+                Next::Act(next) => self.count_action(board_len, next),
+            });
+        }
+        total
+    }
+
+    fn count_street_end(&self, board_len: usize, invested: [f64; 2], aggressor: Option<usize>) -> NodeCount {
+        if board_len == 5 {
+            return NodeCount { terminal: 1, ..Default::default() };
+        }
+
+        let child = if invested[0] >= self.stack {
+            self.count_street_end(board_len + 1, invested, aggressor)
+        } else {
+            self.count_street(board_len + 1, invested, aggressor)
+        };
+        let mut total = child.times(52 - board_len as u64);
+        total.chance += 1;
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn actions(node: &Node) -> &[Action] {
+        match node {
+            Node::Action { actions, .. } => actions,
+            _ => panic!("not an action node"),
+        }
+    }
+
+    #[test]
+    fn test_game_tree() {
+        let board = Board::from_str("Ah Td 6c 2s 3h").unwrap();
+        let sizes = BetSizes { bet: vec![0.5, 1.0], raise: vec![1.0] };
+        let tree = GameTree::new(&board, 100.0, 400.0, &TreeConfig::new(sizes)).unwrap();
+
+        let root = tree.root();
+        assert_eq!(actions(root), [Action::Check, Action::Bet(50.0), Action::Bet(100.0), Action::AllIn(400.0)]);
+        // Raising the pot sized bet to 400 puts in more than the threshold, leaving only all-in.
+        let facing = &root.children()[2];
+        assert_eq!(actions(facing), [Action::Fold, Action::Call, Action::AllIn(400.0)]);
+        assert!(matches!(facing.children()[0], Node::Fold { player: 1, invested: 0.0 }));
+        assert!(matches!(facing.children()[1], Node::Showdown { invested: 100.0 }));
+
+        assert_eq!(tree.count(), GameTree::estimate(&board, 100.0, 400.0, &TreeConfig::new(BetSizes {
+            bet: vec![0.5, 1.0], raise: vec![1.0],
+        })).unwrap());
+    }
+
+    #[test]
+    fn test_raise_cap_and_donk_bets() {
+        let board = Board::from_str("Ah Td 6c 2s").unwrap();
+        let mut config = TreeConfig::new(BetSizes { bet: vec![0.5], raise: vec![0.5] });
+        config.raise_cap = 1;
+        config.all_in_threshold = 1.0;
+        let tree = GameTree::new(&board, 100.0, 10_000.0, &config).unwrap();
+
+        // Bet, raise and then only fold, call or all-in is gone with the cap reached.
+        let raised = &tree.root().children()[1].children()[2];
+        assert_eq!(actions(raised), [Action::Fold, Action::Call]);
+
+        // In position bets the turn and is called, out of position can only check the river.
+        let check_bet_call = &tree.root().children()[0].children()[1].children()[1];
+        let Node::Chance { cards, children } = check_bet_call else { panic!("not a chance node") };
+        assert_eq!(cards.len(), 48);
+        assert_eq!(actions(&children[0]), [Action::Check]);
+
+        config.donk_bets = true;
+        let tree = GameTree::new(&board, 100.0, 10_000.0, &config).unwrap();
+        let river = &tree.root().children()[0].children()[1].children()[1].children()[0];
+        assert_eq!(actions(river).len(), 3);
+
+        let estimate = GameTree::estimate(&board, 100.0, 10_000.0, &config).unwrap();
+        assert_eq!(tree.count(), estimate);
+        assert_eq!(estimate.action as usize, tree.action_nodes());
+    }
+
+    #[test]
+    fn test_invalid_tree() {
+        let board = Board::from_str("Ah Td 6c 2s 3h").unwrap();
+        let config = TreeConfig::default();
+        assert!(matches!(GameTree::estimate(&board, 0.0, 100.0, &config), Err(Error::InvalidTree(_))));
+        assert!(matches!(GameTree::new(&board, 100.0, -1.0, &config), Err(Error::InvalidTree(_))));
+        for size in [0.0, f64::NAN] {
+            let config = TreeConfig::new(BetSizes { bet: vec![size], raise: vec![1.0] });
+            assert!(matches!(GameTree::estimate(&board, 100.0, 100.0, &config), Err(Error::InvalidTree(_))));
+        }
+
+        let limited = TreeConfig { max_nodes: 10, ..TreeConfig::default() };
+        assert!(matches!(GameTree::new(&board, 100.0, 100.0, &limited), Err(Error::TreeTooLarge(_, 10))));
+
+        // With no stack behind there is nothing to bet.
+        let tree = GameTree::new(&board, 100.0, 0.0, &config).unwrap();
+        assert_eq!(actions(tree.root()), [Action::Check]);
+    }
+}